@@ -1,10 +1,13 @@
+use std::path::PathBuf;
+use bevy::asset::io::file::FileAssetReader;
 use bevy::prelude::*;
-use crate::config::{ENEMY_SPRITE_PACK, WATER_PACK, PLAYER_SPRITE_PACK, TERRAIN_PACK, DETAILS_PACK};
+use serde::de::DeserializeOwned;
+use crate::logging::ASSETS_TARGET;
+use crate::config::{WATER_PACK, PLAYER_SPRITE_PACK, TERRAIN_PACK, DETAILS_PACK};
 
 #[derive(Resource)]
 pub struct TextureGameAssets{
     pub player_handler: Option<Handle<Image>>,
-    pub enemy_handler: Option<Handle<Image>>,
     pub map_handler: Option<Handle<Image>>,
     pub terrain_handler: Option<Handle<Image>>,
     pub details_handler: Option<Handle<Image>>,
@@ -23,7 +26,6 @@ impl Default for TextureGameAssets{
     fn default() -> Self {
         Self{
             player_handler: None,
-            enemy_handler: None,
             map_handler: None,
             terrain_handler: None,
             details_handler: None,
@@ -31,14 +33,34 @@ impl Default for TextureGameAssets{
     }
 }
 
+/// Where a file in `assets/` is read from: next to the manifest when run
+/// through cargo, next to the executable otherwise, as for the `AssetServer`.
+pub fn asset_path(file: &str) -> PathBuf {
+    FileAssetReader::new("assets").root_path().join(file)
+}
+
+/// Reads a RON data file from `assets/` when the game starts, so items,
+/// enemies, loot, upgrades and weapons can be changed without a rebuild. The
+/// game cannot run without them, so a missing or invalid file stops it with
+/// an error naming the file.
+pub fn load_data<T: DeserializeOwned>(file: &str) -> T {
+    let path = asset_path(file);
+    let data = std::fs::read_to_string(&path)
+        .map_err(|err| format!("{} could not be read: {}", path.display(), err))
+        .and_then(|text| ron::from_str(&text).map_err(|err| format!("{} is invalid: {}", path.display(), err)));
+    data.unwrap_or_else(|message| {
+        error!(target: ASSETS_TARGET, "{}", message);
+        panic!("{}", message);
+    })
+}
+
 fn load_assets(mut scene_assets: ResMut<TextureGameAssets>,asset_server: Res<AssetServer>){
 
     *scene_assets = TextureGameAssets{
         player_handler: Some(asset_server.load(PLAYER_SPRITE_PACK)),
-        enemy_handler: Some(asset_server.load(ENEMY_SPRITE_PACK)),
         map_handler: Some(asset_server.load(WATER_PACK)),
         terrain_handler: Some(asset_server.load(TERRAIN_PACK)),
         details_handler: Some(asset_server.load(DETAILS_PACK)),
     };
-    println!("Assets Loaded!");
+    info!(target: ASSETS_TARGET, "Assets loaded");
 }
\ No newline at end of file
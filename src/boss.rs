@@ -0,0 +1,262 @@
+use std::f32::consts::TAU;
+use bevy::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+use crate::combat::{DamageDealt, Team};
+use crate::config::{ENEMY_SPEED, MAX_ENEMIES, PLAYER_RADIUS};
+use crate::enemy::{enemy_bundle, remove_dead_enemies, use_abilities, Difficulty, Enemy, EnemyAbility, EnemyDatabase, EnemyKilled, EnemyState, EnemyType};
+use crate::inventory::pickup_bundle;
+use crate::logging::ENEMY_TARGET;
+use crate::loot::{drop_loot, LootDrop, LOOT_SCATTER};
+use crate::player::Player;
+use crate::rng::GameRng;
+use crate::timestep::SimSet;
+
+/// How far from the boss summoned orcs appear.
+const SUMMON_SPREAD: f32 = 150.0;
+const TELEGRAPH_COLOR: Color = Color::srgb(1.0, 0.25, 0.1);
+const SUMMON_COLOR: Color = Color::srgb(0.7, 0.3, 0.9);
+
+/// Mini-boss encounters. Enemies with a `boss` entry in `assets/enemies.ron`
+/// fight in phases once the player comes close, winding up special attacks
+/// before they land, and drop guaranteed rewards when beaten.
+#[derive(Default)]
+pub struct BossPlugin {
+    pub headless: bool,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct BossDef {
+    pub name: String,
+    /// How close the player must be for the fight, and its health bar, to start.
+    pub engage_range: f32,
+    /// In order of falling health.
+    pub phases: Vec<BossPhase>,
+    /// Dropped when it is beaten, on top of its loot table. They do not expire.
+    #[serde(default)]
+    pub rewards: Vec<LootDrop>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct BossPhase {
+    /// Fraction of max health at or below which the phase starts.
+    pub health: f32,
+    /// Time between two special attacks.
+    pub cooldown: f32,
+    /// Used in turn.
+    pub attacks: Vec<BossAttack>,
+}
+
+/// Special attacks, each shown for `windup` seconds before it lands.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub enum BossAttack {
+    /// Hits the player if within `radius` of the boss.
+    Slam { radius: f32, damage: i32, windup: f32 },
+    /// Rushes towards where the player stood at the start of the windup;
+    /// `speed` scales its chase speed.
+    Charge { speed: f32, duration: f32, damage: i32, windup: f32 },
+    /// Calls `count` enemies of type `enemy` around it, as far as the enemy
+    /// cap allows.
+    Summon { enemy: String, count: u32, windup: f32 },
+}
+
+/// Encounter state of a living boss.
+#[derive(Component, Clone, Debug)]
+pub struct Boss {
+    pub name: String,
+    pub max_health: i32,
+    pub phase: usize,
+    /// Whether the player is close enough to be fighting it.
+    pub engaged: bool,
+    /// Attack being wound up, where it is aimed and the time left before it lands.
+    pub windup: Option<(BossAttack, Vec2, f32)>,
+    next_attack: usize,
+    cooldown: f32,
+}
+
+/// Sent when a boss dies, after its rewards drop.
+#[derive(Event, Clone, Debug)]
+pub struct BossDefeated {
+    pub enemy_type: EnemyType,
+    pub name: String,
+    pub position: Vec2,
+}
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<BossDefeated>()
+            .add_systems(FixedUpdate, (
+                (start_encounters, fight_bosses).chain()
+                    .after(use_abilities)
+                    .before(remove_dead_enemies),
+                reward_bosses.after(drop_loot),
+            ).in_set(SimSet::Enemy));
+        if !self.headless {
+            app.add_systems(Update, draw_telegraphs);
+        }
+    }
+}
+
+impl BossPlugin {
+    /// Boss fights only: no telegraph effects.
+    pub fn headless() -> Self {
+        Self { headless: true }
+    }
+}
+
+impl BossAttack {
+    pub fn windup(&self) -> f32 {
+        match self {
+            BossAttack::Slam { windup, .. } | BossAttack::Charge { windup, .. } | BossAttack::Summon { windup, .. } => *windup,
+        }
+    }
+}
+
+impl BossDef {
+    /// Phase for the given fraction of health left.
+    pub fn phase_at(&self, fraction: f32) -> usize {
+        self.phases.iter().rposition(|phase| fraction <= phase.health).unwrap_or(0)
+    }
+}
+
+fn start_encounters(
+    mut commands: Commands,
+    enemies: Res<EnemyDatabase>,
+    query: Query<(Entity, &Enemy, &EnemyType), Without<Boss>>,
+) {
+    for (entity, enemy, enemy_type) in &query {
+        let Some(def) = enemies.get(enemy_type) else { continue; };
+        let Some(boss) = &def.boss else { continue; };
+        commands.entity(entity).insert(Boss {
+            name: boss.name.clone(),
+            max_health: enemy.max_health,
+            phase: 0,
+            engaged: false,
+            windup: None,
+            next_attack: 0,
+            cooldown: boss.phases.first().map_or(0.0, |phase| phase.cooldown),
+        });
+    }
+}
+
+pub(crate) fn fight_bosses(
+    mut commands: Commands,
+    time: Res<Time>,
+    (enemies, difficulty): (Res<EnemyDatabase>, Res<Difficulty>),
+    mut damage: EventWriter<DamageDealt>,
+    player_query: Query<(Entity, &Transform), With<Player>>,
+    mut boss_query: Query<(&Enemy, &EnemyType, &Transform, &mut Boss, &mut EnemyAbility), Without<Player>>,
+    enemy_query: Query<(), With<Enemy>>,
+) {
+    let Ok((player, player_transform)) = player_query.get_single() else { return; };
+    let player_position = player_transform.translation.truncate();
+    let delta = time.delta_seconds();
+    let mut alive = enemy_query.iter().len();
+
+    for (enemy, enemy_type, transform, mut boss, mut ability) in &mut boss_query {
+        let Some(def) = enemies.get(enemy_type) else { continue; };
+        let Some(boss_def) = &def.boss else { continue; };
+        let position = transform.translation.truncate();
+        boss.engaged = position.distance(player_position) <= boss_def.engage_range;
+
+        let phase = boss_def.phase_at(enemy.health as f32 / boss.max_health as f32);
+        if phase != boss.phase {
+            boss.phase = phase;
+            boss.next_attack = 0;
+            info!(target: ENEMY_TARGET, "{} enters phase {}", boss.name, phase + 1);
+        }
+
+        if let Some((attack, target, time_left)) = boss.windup.take() {
+            if time_left > delta {
+                boss.windup = Some((attack, target, time_left - delta));
+                continue;
+            }
+            match attack {
+                BossAttack::Slam { radius, damage: amount, .. } => {
+                    if position.distance(player_position) <= radius + PLAYER_RADIUS {
+                        damage.send(DamageDealt { target: player, amount, team: Team::Enemy });
+                    }
+                }
+                BossAttack::Charge { speed, duration, damage: amount, .. } => {
+                    let direction = (target - position).try_normalize().unwrap_or(Vec2::X);
+                    let speed = ENEMY_SPEED * def.speed * speed * difficulty.speed_multiplier();
+                    ability.charge(direction, speed, duration, amount);
+                }
+                BossAttack::Summon { enemy, count, .. } => {
+                    let Some(summoned) = enemies.get(&EnemyType::new(&enemy)) else {
+                        warn!(target: ENEMY_TARGET, "{} tried to summon unknown enemy type {}", boss.name, enemy);
+                        continue;
+                    };
+                    let health = (summoned.max_health as f32 * difficulty.health_multiplier()) as i32;
+                    // Spots stay spread for the full count, even when the cap cuts it short
+                    let spawned = (count as usize).min(MAX_ENEMIES.saturating_sub(alive));
+                    for index in 0..spawned {
+                        let offset = Vec2::from_angle(TAU * index as f32 / count as f32) * SUMMON_SPREAD;
+                        commands.spawn(enemy_bundle(EnemyType::new(&enemy), EnemyState::default(), position + offset, health));
+                    }
+                    alive += spawned;
+                    debug!(target: ENEMY_TARGET, "{} summoned {} {}", boss.name, spawned, enemy);
+                }
+            }
+            continue;
+        }
+
+        if !boss.engaged {
+            continue;
+        }
+        boss.cooldown = (boss.cooldown - delta).max(0.0);
+        let Some(phase) = boss_def.phases.get(boss.phase) else { continue; };
+        if boss.cooldown > 0.0 || ability.is_busy() || phase.attacks.is_empty() {
+            continue;
+        }
+        let attack = phase.attacks[boss.next_attack % phase.attacks.len()].clone();
+        boss.next_attack += 1;
+        boss.cooldown = phase.cooldown;
+        ability.hold(attack.windup());
+        boss.windup = Some((attack.clone(), player_position, attack.windup()));
+    }
+}
+
+fn reward_bosses(
+    mut commands: Commands,
+    mut killed: EventReader<EnemyKilled>,
+    enemies: Res<EnemyDatabase>,
+    mut rng: ResMut<GameRng>,
+    mut defeated: EventWriter<BossDefeated>,
+) {
+    for event in killed.read() {
+        let Some(boss) = enemies.get(&event.enemy_type).and_then(|def| def.boss.as_ref()) else { continue; };
+        for reward in &boss.rewards {
+            let count = reward.roll_count(&mut rng);
+            let offset = Vec2::new(rng.gen_range(-LOOT_SCATTER..LOOT_SCATTER), rng.gen_range(-LOOT_SCATTER..LOOT_SCATTER));
+            commands.spawn(pickup_bundle(&reward.item, count, event.position + offset));
+        }
+        info!(target: ENEMY_TARGET, "{} defeated", boss.name);
+        defeated.send(BossDefeated {
+            enemy_type: event.enemy_type.clone(),
+            name: boss.name.clone(),
+            position: event.position,
+        });
+    }
+}
+
+/// Warning shapes for attacks being wound up, filling in as they near.
+fn draw_telegraphs(mut gizmos: Gizmos, query: Query<(&Transform, &Boss)>) {
+    for (transform, boss) in &query {
+        let Some((attack, target, time_left)) = &boss.windup else { continue; };
+        let position = transform.translation.truncate();
+        let progress = 1.0 - time_left / attack.windup().max(f32::EPSILON);
+        match attack {
+            BossAttack::Slam { radius, .. } => {
+                gizmos.circle_2d(position, *radius, TELEGRAPH_COLOR.with_alpha(0.4));
+                gizmos.circle_2d(position, radius * progress, TELEGRAPH_COLOR);
+            }
+            BossAttack::Charge { .. } => {
+                gizmos.line_2d(position, *target, TELEGRAPH_COLOR.with_alpha(0.4 + 0.6 * progress));
+            }
+            BossAttack::Summon { .. } => {
+                gizmos.circle_2d(position, SUMMON_SPREAD, SUMMON_COLOR.with_alpha(0.4 + 0.6 * progress));
+            }
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use bevy::prelude::*;
+use bevy_pancam::PanCam;
+use noise::{NoiseFn, Perlin};
+use crate::config::{CAMERA_DEADZONE, CAMERA_MAX_ZOOM, CAMERA_MIN_ZOOM, CAMERA_SMOOTHING, SHAKE_MAX_ANGLE, SHAKE_MAX_OFFSET, TRAUMA_DECAY};
+use crate::inventory::InventoryOpen;
+use crate::player::{Player, PlayerInput};
+use crate::terrain::{tile_world_size, TerrainGenerated, TerrainMap};
+use crate::timestep::InterpolationSet;
+use crate::GameStatus;
+
+const RECENTER_KEY: KeyCode = KeyCode::KeyC;
+const SHAKE_FREQUENCY: f64 = 18.0;
+/// Jumps further than this (new game, load, teleport) snap instead of gliding.
+const SNAP_DISTANCE: f32 = 2000.0;
+
+/// Spawns the game camera and keeps it on the player. Panning with the mouse
+/// (through `PanCam`) switches to free-look until the player moves again or
+/// presses C.
+pub struct CameraPlugin;
+
+#[derive(Component)]
+pub struct CameraController {
+    /// How quickly the camera catches up, per second. Zero snaps to the player.
+    pub smoothing: f32,
+    /// Half size of the box the player can move in without the camera following.
+    /// Zero disables it.
+    pub deadzone: Vec2,
+    pub free_look: bool,
+    focus: Vec2,
+    /// Translation written last frame, to spot when something else (panning) moved the camera.
+    written: Vec2,
+}
+
+/// Trauma based screen shake: offset grows with the square of trauma, which
+/// decays over time. Combat adds trauma on hits and explosions.
+#[derive(Resource)]
+pub struct ScreenShake {
+    trauma: f32,
+    time: f64,
+    noise: Perlin,
+}
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ScreenShake::default())
+            .add_systems(PostStartup, spawn_camera)
+            .add_systems(Update, (
+                toggle_panning,
+                update_camera_bounds.run_if(on_event::<TerrainGenerated>()),
+            ))
+            .add_systems(PostUpdate, follow_player
+                .after(InterpolationSet)
+                .before(TransformSystem::TransformPropagate));
+    }
+}
+
+impl Default for CameraController {
+    fn default() -> Self {
+        Self {
+            smoothing: CAMERA_SMOOTHING,
+            deadzone: Vec2::from(CAMERA_DEADZONE),
+            free_look: false,
+            focus: Vec2::ZERO,
+            written: Vec2::ZERO,
+        }
+    }
+}
+
+impl CameraController {
+    /// Go back to following the player.
+    pub fn recenter(&mut self) {
+        self.free_look = false;
+    }
+}
+
+impl Default for ScreenShake {
+    fn default() -> Self {
+        Self { trauma: 0.0, time: 0.0, noise: Perlin::new(0) }
+    }
+}
+
+impl ScreenShake {
+    /// Adds trauma in 0..=1; a hard hit is around 0.5.
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).clamp(0.0, 1.0);
+    }
+    pub fn trauma(&self) -> f32 {
+        self.trauma
+    }
+
+    /// Offset and rotation for this frame, in screen units before zoom.
+    fn sample(&mut self, delta: f32) -> (Vec2, f32) {
+        self.time += delta as f64;
+        self.trauma = (self.trauma - TRAUMA_DECAY * delta).max(0.0);
+        let shake = self.trauma * self.trauma;
+        if shake == 0.0 {
+            return (Vec2::ZERO, 0.0);
+        }
+        let t = self.time * SHAKE_FREQUENCY;
+        let sample = |channel: f64| self.noise.get([t, channel]) as f32;
+        let offset = Vec2::new(sample(0.0), sample(10.0)) * SHAKE_MAX_OFFSET * shake;
+        (offset, sample(20.0) * SHAKE_MAX_ANGLE * shake)
+    }
+}
+
+fn spawn_camera(mut commands: Commands) {
+    commands.spawn((
+        Camera2dBundle::default(),
+        PanCam {
+            // Zooming towards the cursor would move the camera and drop out of follow
+            zoom_to_cursor: false,
+            min_scale: CAMERA_MIN_ZOOM,
+            max_scale: CAMERA_MAX_ZOOM,
+            ..default()
+        },
+        CameraController::default(),
+    ));
+}
+
+/// Menus are clicked with the same buttons that pan, so panning only works in game.
+fn toggle_panning(state: Res<State<GameStatus>>, inventory_open: Res<InventoryOpen>, mut query: Query<&mut PanCam>) {
+    // Dragging items would also drag the view
+    let enabled = *state.get() == GameStatus::Started && !inventory_open.0;
+    for mut pan_cam in &mut query {
+        if pan_cam.enabled != enabled {
+            pan_cam.enabled = enabled;
+        }
+    }
+}
+
+fn update_camera_bounds(terrain: Res<TerrainMap>, mut query: Query<&mut PanCam>) {
+    let tile = tile_world_size();
+    for mut pan_cam in &mut query {
+        pan_cam.min_x = -tile.x / 2.0;
+        pan_cam.min_y = -tile.y / 2.0;
+        pan_cam.max_x = (terrain.rows as f32 - 0.5) * tile.x;
+        pan_cam.max_y = (terrain.columns as f32 - 0.5) * tile.y;
+    }
+}
+
+fn follow_player(
+    time: Res<Time>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    input: Res<PlayerInput>,
+    mut shake: ResMut<ScreenShake>,
+    player_query: Query<&Transform, (With<Player>, Without<CameraController>)>,
+    mut camera_query: Query<(&mut Transform, &mut CameraController, &mut OrthographicProjection, Option<&PanCam>)>,
+) {
+    let Ok(player) = player_query.get_single() else { return; };
+    let Ok((mut transform, mut controller, mut projection, pan_cam)) = camera_query.get_single_mut() else { return; };
+    let delta = time.delta_seconds();
+
+    // Anything that moved the camera since last frame was the player panning
+    let current = transform.translation.truncate();
+    if current.distance_squared(controller.written) > 0.01 {
+        controller.free_look = true;
+        controller.focus = current;
+    }
+    let moving = input.up || input.down || input.left || input.right;
+    if keyboard.just_pressed(RECENTER_KEY) || (controller.free_look && moving) {
+        controller.recenter();
+    }
+
+    let scale = projection.scale.clamp(CAMERA_MIN_ZOOM, CAMERA_MAX_ZOOM);
+    if projection.scale != scale {
+        projection.scale = scale;
+    }
+
+    if !controller.free_look {
+        let offset = player.translation.truncate() - controller.focus;
+        let target = controller.focus + offset - offset.clamp(-controller.deadzone, controller.deadzone);
+        controller.focus = if controller.smoothing > 0.0 && offset.length() < SNAP_DISTANCE {
+            controller.focus.lerp(target, 1.0 - (-controller.smoothing * delta).exp())
+        } else {
+            target
+        };
+    }
+    if let Some(pan_cam) = pan_cam {
+        controller.focus = clamp_to_bounds(controller.focus, projection.area.half_size(), pan_cam);
+    }
+
+    let (offset, angle) = shake.sample(delta);
+    let position = controller.focus + offset * projection.scale;
+    transform.translation.x = position.x;
+    transform.translation.y = position.y;
+    transform.rotation = Quat::from_rotation_z(angle);
+    controller.written = position;
+}
+
+/// Keeps the view inside the bounds, or centred on them if the view is larger.
+fn clamp_to_bounds(focus: Vec2, half_view: Vec2, pan_cam: &PanCam) -> Vec2 {
+    let clamp_axis = |value: f32, min: f32, max: f32, half: f32| {
+        if !min.is_finite() || !max.is_finite() {
+            value
+        } else if max - min <= half * 2.0 {
+            (min + max) / 2.0
+        } else {
+            value.clamp(min + half, max - half)
+        }
+    };
+    Vec2::new(
+        clamp_axis(focus.x, pan_cam.min_x, pan_cam.max_x, half_view.x),
+        clamp_axis(focus.y, pan_cam.min_y, pan_cam.max_y, half_view.y),
+    )
+}
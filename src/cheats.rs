@@ -0,0 +1,184 @@
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use crate::config::PLAYER_MAX_STAMINA;
+use crate::enemy::{Enemy, WaveState};
+use crate::inventory::{pickup_bundle, ItemDatabase};
+use crate::player::{Player, PlayerStats};
+use crate::timestep::Interpolated;
+
+pub struct CheatsPlugin;
+
+#[derive(Resource, Default)]
+struct CheatConsole {
+    open: bool,
+    input: String,
+    output: String,
+}
+
+#[derive(Component)]
+struct CheatConsoleRoot;
+#[derive(Component)]
+struct CheatConsoleText;
+
+const CONSOLE_KEY: KeyCode = KeyCode::Backquote;
+const HELP: &str = "heal | stamina | kill_all | wave <n> | tp <x> <y> | give <item> [count]";
+
+impl Plugin for CheatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(CheatConsole::default())
+            .add_systems(PostStartup, cheat_console_spawn)
+            .add_systems(Update, (
+                cheat_console_input,
+                cheat_console_render.after(cheat_console_input),
+            ));
+    }
+}
+
+fn cheat_console_spawn(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(0.0),
+                right: Val::Px(0.0),
+                top: Val::Px(0.0),
+                padding: UiRect::all(Val::Px(6.0)),
+                ..default()
+            },
+            background_color: Color::srgba(0.0, 0.0, 0.0, 0.75).into(),
+            visibility: Visibility::Hidden,
+            z_index: ZIndex::Global(100),
+            ..default()
+        },
+        CheatConsoleRoot,
+    )).with_children(|parent| {
+        parent.spawn((
+            TextBundle::from_section("", TextStyle { font_size: 14.5, ..default() }),
+            CheatConsoleText,
+        ));
+    });
+}
+
+fn cheat_console_input(
+    mut console: ResMut<CheatConsole>,
+    mut key_events: EventReader<KeyboardInput>,
+    mut commands: Commands,
+    (mut wave, items): (ResMut<WaveState>, Res<ItemDatabase>),
+    mut player_query: Query<(&mut Player, &PlayerStats, &mut Transform, Option<&mut Interpolated>)>,
+    mut enemy_query: Query<&mut Enemy>,
+) {
+    for event in key_events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        if event.key_code == CONSOLE_KEY {
+            console.open = !console.open;
+            console.input.clear();
+            continue;
+        }
+        if !console.open {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Enter => {
+                let line = std::mem::take(&mut console.input);
+                console.output = run_command(&line, &mut commands, &mut wave, &items, &mut player_query, &mut enemy_query);
+            }
+            Key::Backspace => {
+                console.input.pop();
+            }
+            Key::Space => console.input.push(' '),
+            Key::Character(chars) => console.input.push_str(chars),
+            _ => {}
+        }
+    }
+}
+
+fn run_command(
+    line: &str,
+    commands: &mut Commands,
+    wave: &mut WaveState,
+    items: &ItemDatabase,
+    player_query: &mut Query<(&mut Player, &PlayerStats, &mut Transform, Option<&mut Interpolated>)>,
+    enemy_query: &mut Query<&mut Enemy>,
+) -> String {
+    let mut args = line.split_whitespace();
+    let Some(command) = args.next() else { return String::new(); };
+
+    match command {
+        "help" => HELP.to_string(),
+        "heal" => {
+            for (mut player, stats, _, _) in player_query.iter_mut() {
+                player.health = stats.max_health;
+            }
+            "Player healed".to_string()
+        }
+        "stamina" => {
+            for (mut player, _, _, _) in player_query.iter_mut() {
+                player.stamina = PLAYER_MAX_STAMINA;
+            }
+            "Stamina refilled".to_string()
+        }
+        "kill_all" => {
+            // Killed on the next tick like any other death, so they count and drop loot
+            let mut killed = 0;
+            for mut enemy in enemy_query.iter_mut() {
+                enemy.health = 0;
+                killed += 1;
+            }
+            format!("Killed {} enemies", killed)
+        }
+        "wave" => match args.next().and_then(|arg| arg.parse::<u32>().ok()) {
+            Some(number) if number > 0 => {
+                wave.wave = number;
+                format!("Wave set to {}", number)
+            }
+            _ => "Usage: wave <n>".to_string(),
+        },
+        "tp" => {
+            let x = args.next().and_then(|arg| arg.parse::<f32>().ok());
+            let y = args.next().and_then(|arg| arg.parse::<f32>().ok());
+            match (x, y) {
+                (Some(x), Some(y)) => {
+                    for (_, _, mut transform, interpolated) in player_query.iter_mut() {
+                        transform.translation.x = x;
+                        transform.translation.y = y;
+                        if let Some(mut interpolated) = interpolated {
+                            interpolated.teleport(transform.translation);
+                        }
+                    }
+                    format!("Teleported to {{ x : {}, y : {} }}", x as i32, y as i32)
+                }
+                _ => "Usage: tp <x> <y>".to_string(),
+            }
+        }
+        "give" => {
+            let item = args.next().unwrap_or_default();
+            let count = args.next().and_then(|arg| arg.parse::<u32>().ok()).unwrap_or(1);
+            let Some(def) = items.get(item) else { return format!("Unknown item '{}'", item); };
+            // Dropped on the player, so the magnet collects it next tick
+            for (_, _, transform, _) in player_query.iter() {
+                commands.spawn(pickup_bundle(&def.id, count, transform.translation.truncate()));
+            }
+            format!("Gave {} {}", count, def.name)
+        }
+        _ => format!("Unknown command '{}', try: {}", command, HELP),
+    }
+}
+
+fn cheat_console_render(
+    console: Res<CheatConsole>,
+    mut root_query: Query<&mut Visibility, With<CheatConsoleRoot>>,
+    mut text_query: Query<&mut Text, With<CheatConsoleText>>,
+) {
+    if !console.is_changed() {
+        return;
+    }
+    for mut visibility in &mut root_query {
+        *visibility = if console.open { Visibility::Inherited } else { Visibility::Hidden };
+    }
+    for mut text in &mut text_query {
+        text.sections[0].value = format!("{}\n> {}_", console.output, console.input);
+    }
+}
@@ -0,0 +1,50 @@
+use bevy::prelude::*;
+use crate::boss::fight_bosses;
+use crate::enemy::{remove_dead_enemies, use_abilities, Enemy};
+use crate::player::Player;
+use crate::timestep::SimSet;
+
+/// Damage between the player and enemies. Weapons, projectiles and melee hits
+/// all send `DamageDealt`; health only changes here, after enemy abilities and
+/// boss attacks (which heal and hit) and just before dead enemies are removed.
+pub struct CombatPlugin;
+
+/// Side something fights on. Projectiles only hit the other side.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Team {
+    Player,
+    Enemy,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct DamageDealt {
+    pub target: Entity,
+    pub amount: i32,
+    /// Side of the attacker.
+    pub team: Team,
+}
+
+impl Plugin for CombatPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DamageDealt>()
+            .add_systems(FixedUpdate, apply_damage
+                .in_set(SimSet::Enemy)
+                .after(use_abilities)
+                .after(fight_bosses)
+                .before(remove_dead_enemies));
+    }
+}
+
+fn apply_damage(
+    mut damage: EventReader<DamageDealt>,
+    mut enemy_query: Query<&mut Enemy>,
+    mut player_query: Query<&mut Player>,
+) {
+    for hit in damage.read() {
+        if let Ok(mut enemy) = enemy_query.get_mut(hit.target) {
+            enemy.health -= hit.amount;
+        } else if let Ok(mut player) = player_query.get_mut(hit.target) {
+            player.health = (player.health - hit.amount).max(0);
+        }
+    }
+}
@@ -2,9 +2,34 @@
 // Player Speed/Animation Configuration
 pub const NORMAL_SPEED: f32 = 450.0;
 pub const SPECIAL_SPEED:f32 = 800.0;
+pub const PLAYER_MAX_HEALTH: i32 = 100;
+pub const PLAYER_MAX_STAMINA: f32 = 100.0;
+// Stamina lost per second in deep water
+pub const SWIM_STAMINA_DRAIN: f32 = 15.0;
+// Seconds between two swings at vegetation
+pub const HARVEST_COOLDOWN: f32 = 0.4;
+// Pickups within the magnet radius fly to the player and are collected on contact
+pub const PICKUP_MAGNET_RADIUS: f32 = 200.0;
+pub const PICKUP_COLLECT_RADIUS: f32 = 40.0;
+pub const PICKUP_SPEED: f32 = 600.0;
 //--------------------------------------------------------------------------
 // Enemy Speed
-pub const ENEMY_SPEED:f32 = 6.35;
+pub const ENEMY_SPEED:f32 = 381.0;
+pub const ENEMY_CHASE_RANGE:f32 = 375.0;
+// Rough size of an enemy body, for weapon hits
+pub const ENEMY_RADIUS: f32 = 40.0;
+//--------------------------------------------------------------------------
+// Weapons
+pub const MAX_WEAPONS: usize = 4;
+// How far auto-aimed weapons look for a target
+pub const WEAPON_RANGE: f32 = 800.0;
+// Side of the cells enemies are bucketed into for nearest-enemy queries
+pub const SPATIAL_CELL_SIZE: f32 = 256.0;
+//--------------------------------------------------------------------------
+// Projectiles
+pub const PROJECTILE_LIFETIME: f32 = 1.5;
+// Rough size of the player body, for enemy projectile hits
+pub const PLAYER_RADIUS: f32 = 30.0;
 //--------------------------------------------------------------------------
 // SPRITE PACKS & SCALE FACTOR
 pub const PLAYER_SPRITE_PACK: &str = "player_sprite_pack.png";
@@ -15,17 +40,66 @@ pub const DETAILS_PACK : &str = "nature.png";
 pub const SPRITE_SCALE_FACTOR:u32 = 64;
 pub const ANIMATION_INTERVAL: f32 = 0.1;
 //-------------------------------------------------------------------------
+// Simulation ticks per second
+pub const TICK_RATE: f64 = 60.0;
+//-------------------------------------------------------------------------
+// Saves
+pub const AUTOSAVE_INTERVAL: f32 = 120f32;
+//-------------------------------------------------------------------------
 // Window
 pub const HEIGHT:f32 = 820f32;
 pub const WIDTH:f32 = 1024f32;
 //-------------------------------------------------------------------------
+// Camera
+pub const CAMERA_SMOOTHING: f32 = 6f32;
+pub const CAMERA_DEADZONE: (f32, f32) = (120f32, 80f32);
+pub const CAMERA_MIN_ZOOM: f32 = 0.5;
+pub const CAMERA_MAX_ZOOM: f32 = 3.0;
+pub const SHAKE_MAX_OFFSET: f32 = 24f32;
+pub const SHAKE_MAX_ANGLE: f32 = 0.05;
+pub const TRAUMA_DECAY: f32 = 1.2;
+//-------------------------------------------------------------------------
 // World Generation
 pub const MAP_ROWS: usize = 250;
 pub const MAP_COLUMNS: usize = 250;
 pub const MAP_SCALE_FACTOR: f32 = 2.5;
 pub const MAP_OFFSET: f64 = 10.5;
+pub const CHUNK_SIZE: usize = 16;
+// Tiles around the player that get explored
+pub const VISION_RADIUS: i32 = 8;
+//-------------------------------------------------------------------------
+// Minimap
+pub const MINIMAP_SIZE: f32 = 180f32;
 //-------------------------------------------------------------------------
 // Spawner
 pub const SPAWN_INTERVAL: f32 = 2f32;
 pub const SPAWN_TIME:usize = 10;
-pub const MAX_ENEMIES:usize = 300;
\ No newline at end of file
+pub const MAX_ENEMIES:usize = 300;
+pub const WAVE_DURATION: f32 = 60f32;
+pub const NIGHT_SPAWN_MULTIPLIER: f32 = 1.5;
+//-------------------------------------------------------------------------
+// Loot
+// Seconds dropped loot stays on the ground
+pub const LOOT_LIFETIME: f32 = 60f32;
+//-------------------------------------------------------------------------
+// Leveling
+// Experience needed for level n is XP_BASE * n^XP_GROWTH
+pub const XP_BASE: f32 = 20f32;
+pub const XP_GROWTH: f32 = 1.5;
+pub const UPGRADE_CHOICES: usize = 3;
+//-------------------------------------------------------------------------
+// Day/Night
+// Seconds for a full day
+pub const DAY_LENGTH: f32 = 600f32;
+pub const DAWN_HOUR: f32 = 6f32;
+pub const DUSK_HOUR: f32 = 20f32;
+//-------------------------------------------------------------------------
+// Weather
+// Seconds each weather lasts, and how long it takes to fade into the next
+pub const WEATHER_DURATION: f32 = 90f32;
+pub const WEATHER_TRANSITION: f32 = 10f32;
+//-------------------------------------------------------------------------
+// HUD
+pub const HUD_BAR_WIDTH: f32 = 220f32;
+pub const HUD_BAR_HEIGHT: f32 = 14f32;
+pub const HUD_FONT_SIZE: f32 = 16f32;
\ No newline at end of file
@@ -0,0 +1,124 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::config::{DAWN_HOUR, DAY_LENGTH, DUSK_HOUR};
+use crate::timestep::SimSet;
+
+/// Hours it takes to go from full night to full day at dawn, and back at dusk.
+const TWILIGHT_HOURS: f32 = 1.5;
+/// A new game starts in the morning.
+const START_TIME_OF_DAY: f32 = 7.0 / 24.0;
+
+const NIGHT_TINT: Color = Color::srgba(0.02, 0.03, 0.15, 0.6);
+const TWILIGHT_TINT: Color = Color::srgba(0.55, 0.25, 0.1, 0.25);
+
+/// Game clock advancing with the simulation, and the lighting it drives.
+#[derive(Default)]
+pub struct DayNightPlugin {
+    pub headless: bool,
+}
+
+/// Time of day, ticked in `FixedUpdate` so it stays in step with replays.
+/// Stored in saves.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameClock {
+    pub day: u32,
+    /// Fraction of the day gone by, zero being midnight.
+    pub time_of_day: f32,
+    /// Real seconds for a full day.
+    pub day_length: f32,
+}
+
+#[derive(Component)]
+struct NightTint;
+
+impl Plugin for DayNightPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameClock>()
+            .add_systems(FixedUpdate, advance_clock.in_set(SimSet::World));
+        if !self.headless {
+            app.add_systems(PostStartup, tint_spawn)
+                .add_systems(Update, update_tint);
+        }
+    }
+}
+
+impl DayNightPlugin {
+    /// Clock only: no tint.
+    pub fn headless() -> Self {
+        Self { headless: true }
+    }
+}
+
+impl Default for GameClock {
+    fn default() -> Self {
+        Self {
+            day: 1,
+            time_of_day: START_TIME_OF_DAY,
+            day_length: DAY_LENGTH,
+        }
+    }
+}
+
+impl GameClock {
+    pub fn advance(&mut self, seconds: f32) {
+        if self.day_length <= 0.0 {
+            return;
+        }
+        self.time_of_day += seconds / self.day_length;
+        while self.time_of_day >= 1.0 {
+            self.time_of_day -= 1.0;
+            self.day += 1;
+        }
+    }
+    pub fn hour(&self) -> f32 {
+        self.time_of_day * 24.0
+    }
+    pub fn is_night(&self) -> bool {
+        let hour = self.hour();
+        hour < DAWN_HOUR || hour >= DUSK_HOUR
+    }
+    /// 1 at full day, 0 at full night, ramping through dawn and dusk.
+    pub fn daylight(&self) -> f32 {
+        let hour = self.hour();
+        let ramp = |from: f32| ((hour - from) / TWILIGHT_HOURS).clamp(0.0, 1.0);
+        ramp(DAWN_HOUR - TWILIGHT_HOURS / 2.0) - ramp(DUSK_HOUR - TWILIGHT_HOURS / 2.0)
+    }
+    /// "Day 2 18:30"
+    pub fn label(&self) -> String {
+        let minutes = (self.time_of_day * 24.0 * 60.0) as u32;
+        format!("Day {} {:02}:{:02}", self.day, minutes / 60, minutes % 60)
+    }
+}
+
+fn advance_clock(time: Res<Time>, mut clock: ResMut<GameClock>) {
+    clock.advance(time.delta_seconds());
+}
+
+fn tint_spawn(mut commands: Commands) {
+    // Covers the world but stays behind the rest of the UI
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            z_index: ZIndex::Global(-10),
+            ..default()
+        },
+        NightTint,
+    ));
+}
+
+fn update_tint(clock: Res<GameClock>, mut query: Query<&mut BackgroundColor, With<NightTint>>) {
+    let daylight = clock.daylight();
+    // Warm at the edges of the night, dark blue in the middle of it
+    let twilight = 1.0 - (daylight * 2.0 - 1.0).abs();
+    let tint = NIGHT_TINT.mix(&TWILIGHT_TINT, twilight)
+        .with_alpha(NIGHT_TINT.alpha() * (1.0 - daylight) + TWILIGHT_TINT.alpha() * twilight);
+    for mut color in &mut query {
+        color.0 = tint;
+    }
+}
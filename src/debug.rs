@@ -0,0 +1,230 @@
+use bevy::diagnostic::{DiagnosticsStore, EntityCountDiagnosticsPlugin, FrameTimeDiagnosticsPlugin};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_inspector_egui::quick::WorldInspectorPlugin;
+use crate::config::{CHUNK_SIZE, SPRITE_SCALE_FACTOR};
+use crate::enemy::{Enemy, EnemyDatabase, EnemyState, EnemyType};
+use crate::player::Player;
+use crate::terrain::{tile_world_size, TerrainMap};
+
+pub struct DebugPlugin;
+
+#[derive(Resource, Default)]
+pub struct DebugSettings {
+    pub overlay: bool,
+    pub inspector: bool,
+    pub aggro_radius: bool,
+    pub colliders: bool,
+    pub paths: bool,
+    pub chunk_borders: bool,
+}
+
+#[derive(Component)]
+struct DebugOverlay;
+#[derive(Component)]
+struct DebugText;
+
+// F-key bindings for each debug toggle
+const OVERLAY_KEY: KeyCode = KeyCode::F3;
+const INSPECTOR_KEY: KeyCode = KeyCode::F1;
+const AGGRO_KEY: KeyCode = KeyCode::F4;
+const COLLIDERS_KEY: KeyCode = KeyCode::F6;
+const PATHS_KEY: KeyCode = KeyCode::F7;
+const CHUNKS_KEY: KeyCode = KeyCode::F8;
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugins(FrameTimeDiagnosticsPlugin);
+        }
+        if !app.is_plugin_added::<EntityCountDiagnosticsPlugin>() {
+            app.add_plugins(EntityCountDiagnosticsPlugin);
+        }
+        app.insert_resource(DebugSettings::default())
+            .add_plugins(WorldInspectorPlugin::new().run_if(inspector_enabled))
+            .add_systems(PostStartup, debug_overlay_spawn)
+            .add_systems(Update, (
+                toggle_debug_settings,
+                update_debug_text,
+                draw_aggro_radius.run_if(|settings: Res<DebugSettings>| settings.aggro_radius),
+                draw_colliders.run_if(|settings: Res<DebugSettings>| settings.colliders),
+                draw_paths.run_if(|settings: Res<DebugSettings>| settings.paths),
+                draw_chunk_borders.run_if(|settings: Res<DebugSettings>| settings.chunk_borders),
+            ));
+    }
+}
+
+fn inspector_enabled(settings: Res<DebugSettings>) -> bool {
+    settings.inspector
+}
+
+fn debug_overlay_spawn(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(12.0),
+                bottom: Val::Px(12.0),
+                padding: UiRect::all(Val::Px(6.0)),
+                ..default()
+            },
+            background_color: Color::srgba(0.0, 0.0, 0.0, 0.5).into(),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        DebugOverlay,
+    )).with_children(|parent| {
+        parent.spawn((
+            TextBundle::from_section("", TextStyle { font_size: 14.5, ..default() }),
+            DebugText,
+        ));
+    });
+}
+
+fn toggle_debug_settings(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<DebugSettings>,
+    mut query: Query<&mut Visibility, With<DebugOverlay>>,
+) {
+    if keyboard.just_pressed(OVERLAY_KEY) {
+        settings.overlay = !settings.overlay;
+    }
+    if keyboard.just_pressed(INSPECTOR_KEY) {
+        settings.inspector = !settings.inspector;
+    }
+    if keyboard.just_pressed(AGGRO_KEY) {
+        settings.aggro_radius = !settings.aggro_radius;
+    }
+    if keyboard.just_pressed(COLLIDERS_KEY) {
+        settings.colliders = !settings.colliders;
+    }
+    if keyboard.just_pressed(PATHS_KEY) {
+        settings.paths = !settings.paths;
+    }
+    if keyboard.just_pressed(CHUNKS_KEY) {
+        settings.chunk_borders = !settings.chunk_borders;
+    }
+    if !settings.is_changed() {
+        return;
+    }
+    for mut visibility in &mut query {
+        *visibility = if settings.overlay { Visibility::Inherited } else { Visibility::Hidden };
+    }
+}
+
+fn update_debug_text(
+    settings: Res<DebugSettings>,
+    diagnostics: Res<DiagnosticsStore>,
+    terrain: Option<Res<TerrainMap>>,
+    player_query: Query<&Transform, With<Player>>,
+    enemy_query: Query<&EnemyType, With<Enemy>>,
+    mut text_query: Query<&mut Text, With<DebugText>>,
+) {
+    if !settings.overlay {
+        return;
+    }
+
+    let fps = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed())
+        .unwrap_or_default();
+    let frame_time = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FRAME_TIME)
+        .and_then(|frame_time| frame_time.smoothed())
+        .unwrap_or_default();
+    let entities = diagnostics
+        .get(&EntityCountDiagnosticsPlugin::ENTITY_COUNT)
+        .and_then(|count| count.value())
+        .unwrap_or_default();
+
+    let mut enemies_by_type: HashMap<EnemyType, usize> = HashMap::new();
+    for enemy_type in &enemy_query {
+        *enemies_by_type.entry(enemy_type.clone()).or_default() += 1;
+    }
+    let mut enemy_lines: Vec<String> = enemies_by_type
+        .iter()
+        .map(|(enemy_type, count)| format!("  {}: {}", enemy_type.0, count))
+        .collect();
+    enemy_lines.sort();
+
+    let mut value = format!(
+        "FPS: {:.0} ({:.2} ms)\nEntities: {:.0}\nEnemies: {}\n",
+        fps, frame_time, entities, enemy_query.iter().len(),
+    );
+    for line in enemy_lines {
+        value.push_str(&line);
+        value.push('\n');
+    }
+    if let Ok(transform) = player_query.get_single() {
+        let position = transform.translation.truncate();
+        let biome = terrain
+            .as_ref()
+            .and_then(|terrain| terrain.biome_at(position));
+        value.push_str(&format!(
+            "{{ x : {}, y : {} }}\nBiome: {}",
+            position.x as i32,
+            position.y as i32,
+            biome.map_or("Out of bounds".to_string(), |biome| format!("{:?}", biome)),
+        ));
+    }
+
+    for mut text in &mut text_query {
+        text.sections[0].value = value.clone();
+    }
+}
+
+fn draw_aggro_radius(
+    mut gizmos: Gizmos,
+    enemies: Res<EnemyDatabase>,
+    enemy_query: Query<(&Transform, &EnemyState, &EnemyType)>,
+) {
+    for (transform, state, enemy_type) in &enemy_query {
+        let Some(def) = enemies.get(enemy_type) else { continue; };
+        let color = if state.is_chasing() { Color::srgb(1.0, 0.2, 0.2) } else { Color::srgb(1.0, 0.8, 0.2) };
+        gizmos.circle_2d(transform.translation.truncate(), def.detection_range, color);
+    }
+}
+
+fn draw_colliders(
+    mut gizmos: Gizmos,
+    query: Query<&Transform, Or<(With<Player>, With<Enemy>)>>,
+) {
+    for transform in &query {
+        let size = Vec2::splat(SPRITE_SCALE_FACTOR as f32) * transform.scale.truncate();
+        gizmos.rect_2d(transform.translation.truncate(), 0.0, size, Color::srgb(0.2, 1.0, 0.2));
+    }
+}
+
+fn draw_paths(
+    mut gizmos: Gizmos,
+    player_query: Query<&Transform, With<Player>>,
+    enemy_query: Query<(&Transform, &EnemyState), With<Enemy>>,
+) {
+    let Ok(player) = player_query.get_single() else { return; };
+    for (transform, state) in &enemy_query {
+        if state.is_chasing() {
+            gizmos.line_2d(transform.translation.truncate(), player.translation.truncate(), Color::srgb(0.3, 0.6, 1.0));
+        }
+    }
+}
+
+fn draw_chunk_borders(
+    mut gizmos: Gizmos,
+    terrain: Option<Res<TerrainMap>>,
+) {
+    let Some(terrain) = terrain else { return; };
+    let tile = tile_world_size();
+    // Tiles are centered on their grid position, so borders sit half a tile back.
+    let origin = -tile / 2.0;
+    let end = origin + Vec2::new(terrain.rows as f32 * tile.x, terrain.columns as f32 * tile.y);
+    let color = Color::srgba(1.0, 1.0, 1.0, 0.4);
+
+    for x in (0..=terrain.rows).step_by(CHUNK_SIZE) {
+        let x = origin.x + x as f32 * tile.x;
+        gizmos.line_2d(Vec2::new(x, origin.y), Vec2::new(x, end.y), color);
+    }
+    for y in (0..=terrain.columns).step_by(CHUNK_SIZE) {
+        let y = origin.y + y as f32 * tile.y;
+        gizmos.line_2d(Vec2::new(origin.x, y), Vec2::new(end.x, y), color);
+    }
+}
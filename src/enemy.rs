@@ -1,36 +1,181 @@
 use std::cmp::PartialEq;
 use std::f32;
-use bevy::math::vec3;
+use bevy::math::vec2;
 use std::f32::consts::PI;
 use std::fmt::Debug;
 use std::time::Duration;
 use bevy::prelude::*;
 use bevy::time::common_conditions::on_timer;
+use bevy::time::Stopwatch;
+use bevy::utils::HashMap;
 use rand::Rng;
-use crate::assets_loader::TextureGameAssets;
-use crate::config::{ANIMATION_INTERVAL, ENEMY_SPEED, MAX_ENEMIES, SPAWN_INTERVAL, SPAWN_TIME, SPRITE_SCALE_FACTOR};
+use serde::{Deserialize, Serialize};
+use crate::assets_loader::load_data;
+use crate::boss::BossDef;
+use crate::combat::{DamageDealt, Team};
+use crate::projectile::FireProjectile;
+use crate::rng::GameRng;
+use crate::spatial::{index_enemies, EnemyGrid};
+use crate::timestep::{Interpolated, SimSet};
+use crate::config::{ANIMATION_INTERVAL, ENEMY_CHASE_RANGE, ENEMY_RADIUS, ENEMY_SPEED, ENEMY_SPRITE_PACK, MAX_ENEMIES, PLAYER_RADIUS, NIGHT_SPAWN_MULTIPLIER, SPAWN_INTERVAL, SPAWN_TIME, SPRITE_SCALE_FACTOR, WAVE_DURATION};
+use crate::daynight::GameClock;
+use crate::logging::ENEMY_TARGET;
 use crate::player::Player;
-pub struct EnemyPlugin;
+use crate::terrain::{BaseTint, TerrainMap};
+use crate::weather::WeatherState;
 
+/// How long the attack rows play after an ability is used.
+const ATTACK_ANIMATION_TIME: f32 = 0.4;
+/// Hit radius of arrows shot by ranged enemies.
+const ARROW_RADIUS: f32 = 10.0;
+/// Time between two melee hits from the same enemy.
+const MELEE_COOLDOWN: f32 = 1.0;
 
-#[derive(Resource)]
+#[derive(Default)]
+pub struct EnemyPlugin {
+    pub headless: bool,
+}
+
+
+/// Image and atlas layout of each enemy sprite sheet, by file name.
+#[derive(Resource, Default)]
 pub struct GlobalTextureAtlas {
-    pub layout: Option<Handle<TextureAtlasLayout>>,
+    pub sheets: HashMap<String, (Handle<Image>, Handle<TextureAtlasLayout>)>,
 }
 #[derive(Component)]
-pub struct Enemy;
-#[derive(Component,PartialEq,Debug,Default)]
-enum EnemyType{
+pub struct Enemy {
+    pub health: i32,
+    /// Health it spawned with, difficulty and night included. Healing stops there.
+    pub max_health: i32,
+}
+/// Id of an enemy definition in `assets/enemies.ron`. Stored in saves.
+#[derive(Component,Clone,PartialEq,Eq,Hash,Debug,Serialize,Deserialize)]
+#[serde(transparent)]
+pub struct EnemyType(pub String);
+
+/// One enemy in the registry at `assets/enemies.ron`: stats, sprites, spawn
+/// odds, loot and behaviour. New enemies only need an entry there.
+#[derive(Deserialize, Clone, Debug)]
+pub struct EnemyDef {
+    pub id: String,
+    pub max_health: i32,
+    /// Experience the player gains for killing one.
+    pub xp: u32,
+    /// Multiplies `ENEMY_SPEED`.
+    pub speed: f32,
+    /// Applied on top of `max_health` to enemies spawned at night.
+    #[serde(default = "one")]
+    pub night_health_multiplier: f32,
+    /// Applied to chase speed while it is night.
+    #[serde(default = "one")]
+    pub night_speed_multiplier: f32,
+    /// Distance it holds from the player; 0 closes in all the way.
+    #[serde(default)]
+    pub keep_distance: f32,
+    /// How close the player must come before it gives chase.
+    #[serde(default = "chase_range")]
+    pub detection_range: f32,
+    /// Size of its body, for hits in both directions.
+    #[serde(default = "enemy_radius")]
+    pub radius: f32,
+    /// Melee, arrow or charge damage, depending on `ai`.
+    #[serde(default)]
+    pub damage: i32,
+    /// Relative odds of being picked when enemies spawn, by day and at night.
+    pub spawn_weight: u32,
+    pub night_spawn_weight: Option<u32>,
+    /// Table in `assets/loot.ron` rolled when it dies.
+    pub loot: Option<String>,
+    pub sprite: EnemySprite,
+    pub ai: EnemyAi,
+    /// Makes it a mini-boss encounter.
+    pub boss: Option<BossDef>,
+}
+
+/// Where its frames are. `idle`, `run` and `attack` are the first rows of each
+/// animation; four consecutive rows per animation, facing front, back, left
+/// and right. Without attack rows it keeps running while it attacks.
+#[derive(Deserialize, Clone, Debug)]
+pub struct EnemySprite {
+    #[serde(default = "enemy_sheet")]
+    pub sheet: String,
+    #[serde(default = "sheet_columns")]
+    pub columns: usize,
+    #[serde(default = "sheet_rows")]
+    pub rows: usize,
+    pub idle: usize,
+    pub run: usize,
+    #[serde(default)]
+    pub attack: Option<usize>,
+    #[serde(default = "no_tint")]
+    pub tint: (f32, f32, f32),
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum EnemyAi {
+    /// Runs straight at the player and hits on contact.
+    Melee,
+    /// Shoots at the player from up to `range`.
+    Ranged { range: f32, cooldown: f32, projectile_speed: f32 },
+    /// Heals hurt orcs within `radius`.
+    Support { radius: f32, heal: i32, cooldown: f32 },
+    /// Rushes the player once within `range`, at `speed` times its own speed,
+    /// hitting on contact.
+    Charge { range: f32, speed: f32, duration: f32, cooldown: f32 },
+}
+
+#[derive(Resource)]
+pub struct EnemyDatabase {
+    enemies: Vec<EnemyDef>,
+}
+
+/// Cooldown and ongoing use of an enemy's ability.
+#[derive(Component, Default, Clone, Copy, Debug)]
+pub struct EnemyAbility {
+    cooldown: f32,
+    /// Time left on the attack animation.
+    attacking: f32,
+    /// Time left standing still, winding up an attack.
+    hold: f32,
+    charge: Option<Charge>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Charge {
+    direction: Vec2,
+    speed: f32,
+    time_left: f32,
+    damage: i32,
+}
+
+/// Sent when an enemy's health runs out, just before it is despawned.
+#[derive(Event, Clone, Debug)]
+pub struct EnemyKilled {
+    pub enemy_type: EnemyType,
+    pub position: Vec2,
+}
+
+/// Scales how many enemies spawn, how tough they are and how fast they chase.
+/// Fixed for the length of a run: it is picked when a game starts and stored
+/// in saves and replays.
+#[derive(Resource,Clone,Copy,PartialEq,Eq,Hash,Debug,Default,Serialize,Deserialize)]
+pub enum Difficulty {
+    Easy,
     #[default]
-    Slave,
-    MiniBoss,
+    Normal,
+    Hard,
 }
 
-#[derive(Resource,Default)]
-struct EnemyCurrentState(EnemyState);
+#[derive(Resource)]
+pub struct WaveState {
+    pub wave: u32,
+    pub kills: u32,
+    pub elapsed: Stopwatch,
+    timer: Timer,
+}
 
-#[derive(Component,Default, PartialEq, Debug)]
-enum EnemyState {
+#[derive(Component,Default,Clone,Copy,PartialEq,Debug,Serialize,Deserialize)]
+pub enum EnemyState {
     #[default]
     Alive,
     IdleFront,
@@ -46,15 +191,35 @@ enum EnemyState {
 
 impl Plugin for EnemyPlugin {
     fn build(&self, app_si: &mut App) {
-        app_si.insert_resource(GlobalTextureAtlas::default())
-            .insert_resource(EnemyCurrentState::default())
-            .add_systems(Startup,load_assets)
-            .add_systems(Update, (
+        app_si.init_resource::<GlobalTextureAtlas>()
+            .insert_resource(EnemyDatabase::load())
+            .insert_resource(WaveState::default())
+            .init_resource::<Difficulty>()
+            .add_event::<EnemyKilled>()
+            .init_resource::<EnemyGrid>()
+            .add_systems(FixedUpdate, index_enemies.in_set(SimSet::World))
+            .add_systems(FixedUpdate, (
+                advance_wave,
                 spawn_enemy.run_if(on_timer(Duration::from_secs_f32(SPAWN_INTERVAL))),
-                animate_enemies,
-                follow_player
-            ))
+                follow_player,
+                use_abilities,
+                remove_dead_enemies,
+            ).chain().in_set(SimSet::Enemy))
         ;
+        if !self.headless {
+            app_si.add_systems(Startup,load_assets)
+                .add_systems(Update, (
+                    attach_enemy_sprite,
+                    animate_enemies,
+                ));
+        }
+    }
+}
+
+impl EnemyPlugin {
+    /// Spawning and AI only: no sprites or animation.
+    pub fn headless() -> Self {
+        Self { headless: true }
     }
 }
 
@@ -62,12 +227,20 @@ fn spawn_enemy(
     mut commands: Commands,
     player_query : Query<&Transform,With<Player>>,
     enemy_query: Query<&Transform,(With<Enemy>,Without<Player>)>,
-    handler : Res<TextureGameAssets>,
-    mut texture_atlas_layout: ResMut<Assets<TextureAtlasLayout>>,
+    wave: Res<WaveState>,
+    (enemies, difficulty): (Res<EnemyDatabase>, Res<Difficulty>),
+    (clock, weather): (Res<GameClock>, Res<WeatherState>),
+    mut rng: ResMut<GameRng>,
 ) {
 
+    let night = clock.is_night();
     let total_enemies_spawned = enemy_query.iter().len();
-    let enemy_spawn_count = (MAX_ENEMIES-total_enemies_spawned).min(SPAWN_TIME);
+    let mut per_spawn = (SPAWN_TIME + wave.wave as usize - 1) as f32 * difficulty.spawn_multiplier() * weather.spawn_multiplier();
+    if night {
+        per_spawn *= NIGHT_SPAWN_MULTIPLIER;
+    }
+    let per_spawn = per_spawn as usize;
+    let enemy_spawn_count = MAX_ENEMIES.saturating_sub(total_enemies_spawned).min(per_spawn);
 
     if total_enemies_spawned >= MAX_ENEMIES || player_query.is_empty(){
         return;
@@ -76,160 +249,252 @@ fn spawn_enemy(
     for _ in 0..enemy_spawn_count {
 
         let player_pos = player_query.single().translation.truncate();
-        let (x,y) = get_random_pos(player_pos);
-        let enemy_type = EnemyType::generate_random_enemy();
+        let (x,y) = get_random_pos(player_pos, &mut rng);
+        let Some(def) = enemies.roll(&mut rng, night) else { return; };
         let enemy_state = EnemyState::default();
-        let texture = handler.enemy_handler.clone();
-        let layout = TextureAtlasLayout::from_grid(UVec2::splat(SPRITE_SCALE_FACTOR),8,32,None,None);
-        let texture_atlas_layout = texture_atlas_layout.add(layout);
-
-        commands.spawn((
-            SpriteBundle {
-                transform: Transform::from_translation(vec3(x,y,0.0)).with_scale(Vec3::splat(2.5)),
-                texture: texture.unwrap(),
-                ..default()
-            },
+
+        let mut health = def.max_health as f32 * difficulty.health_multiplier();
+        if night {
+            health *= def.night_health_multiplier;
+        }
+        let health = health as i32;
+        commands.spawn(enemy_bundle(EnemyType(def.id.clone()), enemy_state, vec2(x,y), health));
+    }
+    debug!(target: ENEMY_TARGET, "Spawned {} enemies, {} alive", enemy_spawn_count, total_enemies_spawned + enemy_spawn_count);
+}
+
+pub fn enemy_bundle(enemy_type: EnemyType, state: EnemyState, position: Vec2, health: i32) -> impl Bundle {
+    (
+        SpatialBundle::from_transform(Transform::from_translation(position.extend(0.0)).with_scale(Vec3::splat(2.5))),
+        Interpolated::new(position.extend(0.0)),
+        Enemy { health, max_health: health },
+        enemy_type,
+        state,
+        EnemyAbility::default(),
+    )
+}
+
+fn attach_enemy_sprite(
+    mut commands: Commands,
+    query: Query<(Entity, &EnemyType), Added<Enemy>>,
+    (atlas, enemies): (Res<GlobalTextureAtlas>, Res<EnemyDatabase>),
+) {
+    for (entity, enemy_type) in &query {
+        let Some(sprite) = enemies.get(enemy_type).map(|def| &def.sprite) else { continue; };
+        let Some((image, layout)) = atlas.sheets.get(&sprite.sheet) else { continue; };
+        let (red, green, blue) = sprite.tint;
+        let color = Color::srgb(red, green, blue);
+        commands.entity(entity).insert((
+            Sprite { color, ..default() },
+            BaseTint(color),
+            image.clone(),
             TextureAtlas {
-                layout: texture_atlas_layout,
-                index: enemy_type.get_enemy_index(),
+                layout: layout.clone(),
+                index: sprite.idle * sprite.columns,
             },
-            Enemy,
-            enemy_type,
-            enemy_state,
             crate::AnimationTimer(Timer::from_seconds(ANIMATION_INTERVAL, TimerMode::Repeating)),
         ));
     }
-    println!("Total enemy spawned: {}",total_enemies_spawned);
 }
 
 fn animate_enemies(
     time : Res<Time>,
-    mut query : Query<(&mut crate::AnimationTimer, &mut TextureAtlas, &EnemyType, &EnemyState),(With<Enemy>,Without<Player>)>,
+    enemies: Res<EnemyDatabase>,
+    mut query : Query<(&mut crate::AnimationTimer, &mut TextureAtlas, &EnemyType, &EnemyState, &EnemyAbility)>,
 ){
-
-    for (mut timer, mut atlas, enemy_type, enemy_state) in &mut query {
-        timer.tick(time.delta());
-        if (enemy_state == &EnemyState::IdleFront  || enemy_state == &EnemyState::Alive) && enemy_type.is_slave(){
-            if timer.just_finished(){
-                atlas.index = (atlas.index+1)%8
-            }
-        }
-        if enemy_state == &EnemyState::IdleBack && enemy_type.is_slave(){
-            if timer.just_finished() {
-                atlas.index = (atlas.index+1)%8 + 8
-            }
-        }
-        if enemy_state == &EnemyState::IdleLeft && enemy_type.is_slave(){
-            if timer.just_finished(){
-                atlas.index = (atlas.index+1)%8 + 16
-            }
-        }
-        if enemy_state == &EnemyState::IdleRight && enemy_type.is_slave(){
-            if timer.just_finished(){
-                atlas.index = (atlas.index+1)%8 + 24
-            }
-        }
-        if enemy_state == &EnemyState::RunFront && enemy_type.is_slave(){
-            if timer.just_finished(){
-                atlas.index = (atlas.index+1)%8 + 32
-            }
-        }
-        if enemy_state == &EnemyState::RunBack && enemy_type.is_slave(){
-            if timer.just_finished(){
-                atlas.index = (atlas.index+1)%8 + 40
-            }
-        }
-        if enemy_state == &EnemyState::RunLeft && enemy_type.is_slave(){
-            if timer.just_finished(){
-                atlas.index = (atlas.index+1)%8 + 48
-            }
-        }
-        if enemy_state == &EnemyState::RunRight && enemy_type.is_slave(){
-            if timer.just_finished(){
-                atlas.index = (atlas.index+1)%8 + 56
-            }
-        }
-        if (enemy_state == &EnemyState::IdleFront  || enemy_state == &EnemyState::Alive) && !enemy_type.is_slave(){
-            if timer.just_finished(){
-                atlas.index = (atlas.index+1)%8 + 128
-            }
+    for (mut timer, mut atlas, enemy_type, enemy_state, ability) in &mut query {
+        if !timer.tick(time.delta()).just_finished() {
+            continue;
         }
-        if enemy_state == &EnemyState::IdleBack && !enemy_type.is_slave(){
-            if timer.just_finished() {
-                atlas.index = (atlas.index+1)%8 + 136
-            }
-        }
-        if enemy_state == &EnemyState::IdleLeft && !enemy_type.is_slave(){
-            if timer.just_finished(){
-                atlas.index = (atlas.index+1)%8 + 144
-            }
-        }
-        if enemy_state == &EnemyState::IdleRight && !enemy_type.is_slave(){
-            if timer.just_finished(){
-                atlas.index = (atlas.index+1)%8 + 152
-            }
+        let Some(sprite) = enemies.get(enemy_type).map(|def| &def.sprite) else { continue; };
+        let first_row = match sprite.attack.filter(|_| ability.attacking > 0.0) {
+            Some(attack) => attack,
+            None if enemy_state.is_chasing() => sprite.run,
+            None => sprite.idle,
+        };
+        let row = first_row + enemy_state.facing();
+        atlas.index = row * sprite.columns + (atlas.index + 1) % sprite.columns;
+    }
+}
+fn advance_wave(
+    time: Res<Time>,
+    mut wave: ResMut<WaveState>,
+) {
+    wave.elapsed.tick(time.delta());
+    if wave.timer.tick(time.delta()).just_finished() {
+        wave.wave += 1;
+    }
+}
+
+impl EnemyState {
+    pub fn is_chasing(&self) -> bool {
+        match self {
+            EnemyState::RunFront => true,
+            EnemyState::RunBack => true,
+            EnemyState::RunLeft => true,
+            EnemyState::RunRight => true,
+            _ => false
         }
-        if enemy_state == &EnemyState::RunFront && !enemy_type.is_slave(){
-            if timer.just_finished(){
-                atlas.index = (atlas.index+1)%8 + 160
-            }
+    }
+    /// The idle state facing the same way.
+    fn stopped(&self) -> Self {
+        match self {
+            EnemyState::RunFront => EnemyState::IdleFront,
+            EnemyState::RunBack => EnemyState::IdleBack,
+            EnemyState::RunLeft => EnemyState::IdleLeft,
+            EnemyState::RunRight => EnemyState::IdleRight,
+            state => *state,
         }
-        if enemy_state == &EnemyState::RunBack && !enemy_type.is_slave(){
-            if timer.just_finished(){
-                atlas.index = (atlas.index+1)%8 + 168
-            }
+    }
+    /// Offset of the sprite row facing the same way: front, back, left, right.
+    fn facing(&self) -> usize {
+        match self {
+            EnemyState::IdleBack | EnemyState::RunBack => 1,
+            EnemyState::IdleLeft | EnemyState::RunLeft => 2,
+            EnemyState::IdleRight | EnemyState::RunRight => 3,
+            _ => 0,
         }
-        if enemy_state == &EnemyState::RunLeft && !enemy_type.is_slave(){
-            if timer.just_finished(){
-                atlas.index = (atlas.index+1)%8 + 176
-            }
+    }
+}
+
+impl EnemyType {
+    pub fn new(id: &str) -> Self {
+        Self(id.to_string())
+    }
+}
+
+impl EnemyDatabase {
+    /// Reads the enemy definitions from `assets/enemies.ron`.
+    pub fn load() -> Self {
+        let enemies: Vec<EnemyDef> = load_data("enemies.ron");
+        Self { enemies }
+    }
+    pub fn get(&self, enemy_type: &EnemyType) -> Option<&EnemyDef> {
+        self.enemies.iter().find(|def| def.id == enemy_type.0)
+    }
+    pub fn iter(&self) -> impl Iterator<Item = &EnemyDef> {
+        self.enemies.iter()
+    }
+    /// Picks an enemy to spawn by its spawn weight.
+    fn roll(&self, rng: &mut GameRng, night: bool) -> Option<&EnemyDef> {
+        let weight = |def: &EnemyDef| if night { def.night_spawn_weight.unwrap_or(def.spawn_weight) } else { def.spawn_weight };
+        let total: u32 = self.enemies.iter().map(weight).sum();
+        if total == 0 {
+            return None;
         }
-        if enemy_state == &EnemyState::RunRight && !enemy_type.is_slave(){
-            if timer.just_finished(){
-                atlas.index = (atlas.index+1)%8 + 184
+        let mut pick = rng.gen_range(0..total);
+        self.enemies.iter().find(|def| {
+            if pick < weight(def) {
+                return true;
             }
-        }
+            pick -= weight(def);
+            false
+        })
     }
 }
-impl EnemyCurrentState{
-    fn set_state(&mut self,new_state : EnemyState){
-        self.0 = new_state;
+
+impl EnemyAbility {
+    /// Whether it is charging or winding up, and so not chasing.
+    pub fn is_busy(&self) -> bool {
+        self.charge.is_some() || self.hold > 0.0
+    }
+    /// Stands still for `seconds`, playing the attack animation.
+    pub fn hold(&mut self, seconds: f32) {
+        self.hold = seconds;
+        self.attacking = seconds;
+    }
+    /// Rushes along `direction` at `speed` units per second, hitting the
+    /// player on contact.
+    pub fn charge(&mut self, direction: Vec2, speed: f32, duration: f32, damage: i32) {
+        self.charge = Some(Charge { direction, speed, time_left: duration, damage });
     }
 }
 
-impl EnemyType {
-    fn generate_random_enemy() -> Self{
-        let mut rand = rand::thread_rng();
-        let random_enemy = rand.gen_range(0..4);
-        return match random_enemy {
-            0 => EnemyType::MiniBoss,
-            _ => EnemyType::Slave
-        };
+fn one() -> f32 {
+    1.0
+}
+
+fn enemy_radius() -> f32 {
+    ENEMY_RADIUS
+}
+
+fn chase_range() -> f32 {
+    ENEMY_CHASE_RANGE
+}
+
+fn enemy_sheet() -> String {
+    ENEMY_SPRITE_PACK.to_string()
+}
+
+fn sheet_columns() -> usize {
+    8
+}
+
+fn sheet_rows() -> usize {
+    32
+}
+
+fn no_tint() -> (f32, f32, f32) {
+    (1.0, 1.0, 1.0)
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
     }
-    fn get_enemy_index(&self) -> usize{
+    pub fn spawn_multiplier(&self) -> f32 {
         match self {
-            EnemyType::Slave => 0,
-            EnemyType::MiniBoss => 127
+            Difficulty::Easy => 0.75,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.25,
         }
     }
-    fn is_slave(&self) -> bool {
+    pub fn health_multiplier(&self) -> f32 {
         match self {
-            EnemyType::Slave => true,
-            _ => false
+            Difficulty::Easy => 0.75,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.5,
+        }
+    }
+    pub fn speed_multiplier(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.85,
+            Difficulty::Normal => 1.0,
+            Difficulty::Hard => 1.15,
         }
     }
 }
 
-impl Default for GlobalTextureAtlas {
+impl WaveState {
+    pub fn time_in_wave(&self) -> f32 {
+        self.timer.elapsed_secs()
+    }
+    pub fn restore(&mut self, wave: u32, kills: u32, elapsed: f32, time_in_wave: f32) {
+        self.wave = wave;
+        self.kills = kills;
+        self.elapsed.set_elapsed(Duration::from_secs_f32(elapsed));
+        self.timer.set_elapsed(Duration::from_secs_f32(time_in_wave));
+    }
+}
+
+impl Default for WaveState {
     fn default() -> Self {
         Self {
-            layout: None,
+            wave: 1,
+            kills: 0,
+            elapsed: Stopwatch::new(),
+            timer: Timer::from_seconds(WAVE_DURATION, TimerMode::Repeating),
         }
     }
 }
 
-fn get_random_pos(pos: Vec2) -> (f32,f32){
-    let mut rng = rand::thread_rng();
+fn get_random_pos(pos: Vec2, rng: &mut GameRng) -> (f32,f32){
     let angle = rng.gen_range(0.0..PI * 2.0);
     let dist = rng.gen_range(1000.0..5000.0);
 
@@ -244,16 +509,27 @@ fn get_random_pos(pos: Vec2) -> (f32,f32){
 
 fn load_assets(
     mut handle: ResMut<GlobalTextureAtlas>,
+    enemies: Res<EnemyDatabase>,
+    asset_server: Res<AssetServer>,
     mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
 ) {
-
-    let layout = TextureAtlasLayout::from_grid(UVec2::splat(SPRITE_SCALE_FACTOR), 8, 32, None, None);
-    handle.layout = Some(texture_atlas_layouts.add(layout));
+    for sprite in enemies.iter().map(|def| &def.sprite) {
+        if handle.sheets.contains_key(&sprite.sheet) {
+            continue;
+        }
+        let layout = TextureAtlasLayout::from_grid(UVec2::splat(SPRITE_SCALE_FACTOR), sprite.columns as u32, sprite.rows as u32, None, None);
+        let sheet = (asset_server.load(sprite.sheet.clone()), texture_atlas_layouts.add(layout));
+        handle.sheets.insert(sprite.sheet.clone(), sheet);
+    }
 }
 
 fn follow_player(
+    time: Res<Time>,
+    (enemies, difficulty): (Res<EnemyDatabase>, Res<Difficulty>),
+    clock: Res<GameClock>,
+    (weather, terrain): (Res<WeatherState>, Option<Res<TerrainMap>>),
     player_query: Query<&Transform, With<Player>>,
-    mut enemy_query: Query<(&mut Transform, &mut EnemyState), (With<Enemy>,Without<Player>)>,
+    mut enemy_query: Query<(&mut Transform, &mut EnemyState, &EnemyType, &EnemyAbility), Without<Player>>,
 ) {
     if player_query.is_empty() {
         return;
@@ -264,7 +540,14 @@ fn follow_player(
 
     let player_position = player_query.single().translation;
 
-    for (mut enemy_transform, mut enemy_state) in &mut enemy_query {
+    let night = clock.is_night();
+    for (mut enemy_transform, mut enemy_state, enemy_type, ability) in &mut enemy_query {
+        // Charges move on their own in `use_abilities`
+        if ability.is_busy() {
+            continue;
+        }
+        let Some(def) = enemies.get(enemy_type) else { continue; };
+        let chase_range = def.detection_range * weather.detection_multiplier();
         let direction = (player_position - enemy_transform.translation).normalize();
         let distance = distance(player_position, enemy_transform.translation);
 
@@ -274,7 +557,7 @@ fn follow_player(
         let angle = if angle < 0.0 { angle + 360.0 } else { angle };
 
 
-        if distance < 375.0 {
+        if distance < chase_range {
             if 0.0 <= angle && 25f32 >= angle || 335f32 <= angle && 360f32 >= angle {
                 *enemy_state = EnemyState::RunRight;
             }
@@ -287,13 +570,133 @@ fn follow_player(
             if 206f32 <= angle && 334f32 >= angle{
                 *enemy_state = EnemyState::RunFront;
             }
-            enemy_transform.translation += direction * ENEMY_SPEED;
+            // Orcs keeping their distance hold there, and back off when the player closes in
+            let step = if distance > def.keep_distance {
+                1.0
+            } else if distance < def.keep_distance * 0.8 {
+                -1.0
+            } else {
+                *enemy_state = enemy_state.stopped();
+                0.0
+            };
+            let mut speed = ENEMY_SPEED * def.speed * difficulty.speed_multiplier();
+            if night {
+                speed *= def.night_speed_multiplier;
+            }
+            let biome = terrain.as_ref().and_then(|terrain| terrain.biome_at(enemy_transform.translation.truncate()));
+            speed *= biome.map(|biome| biome.movement().speed).unwrap_or(1.0) * weather.speed_multiplier(biome);
+            enemy_transform.translation += direction * step * speed * time.delta_seconds();
         } else {
             *enemy_state = EnemyState::IdleFront;
         }
     }
 }
 
+/// Melee hits, shooting, healing and charging, on each type's cooldown.
+pub(crate) fn use_abilities(
+    time: Res<Time>,
+    (enemies, difficulty): (Res<EnemyDatabase>, Res<Difficulty>),
+    grid: Res<EnemyGrid>,
+    (mut shots, mut damage): (EventWriter<FireProjectile>, EventWriter<DamageDealt>),
+    player_query: Query<(Entity, &Transform), With<Player>>,
+    mut enemy_query: Query<(&mut Enemy, &EnemyType, &mut Transform, &mut EnemyAbility), Without<Player>>,
+) {
+    let Ok((player, player_transform)) = player_query.get_single() else { return; };
+    let player_position = player_transform.translation.truncate();
+    let delta = time.delta_seconds();
+    let mut heals = Vec::new();
+
+    for (_, enemy_type, mut transform, mut ability) in &mut enemy_query {
+        let Some(def) = enemies.get(enemy_type) else { continue; };
+        let position = transform.translation.truncate();
+        let distance = position.distance(player_position);
+        let aim = (player_position - position).try_normalize().unwrap_or(Vec2::X);
+        ability.cooldown = (ability.cooldown - delta).max(0.0);
+        ability.attacking = (ability.attacking - delta).max(0.0);
+        ability.hold = (ability.hold - delta).max(0.0);
+
+        if let Some(charge) = ability.charge {
+            transform.translation += (charge.direction * charge.speed * delta).extend(0.0);
+            ability.attacking = ATTACK_ANIMATION_TIME;
+            let hit = transform.translation.truncate().distance(player_position) <= PLAYER_RADIUS + def.radius;
+            if hit {
+                damage.send(DamageDealt { target: player, amount: charge.damage, team: Team::Enemy });
+            }
+            ability.charge = (!hit && charge.time_left > delta).then_some(Charge { time_left: charge.time_left - delta, ..charge });
+            continue;
+        }
+        if ability.cooldown > 0.0 || ability.hold > 0.0 {
+            continue;
+        }
+
+        match def.ai {
+            EnemyAi::Melee => {
+                if distance > PLAYER_RADIUS + def.radius || def.damage == 0 {
+                    continue;
+                }
+                damage.send(DamageDealt { target: player, amount: def.damage, team: Team::Enemy });
+                ability.cooldown = MELEE_COOLDOWN;
+            }
+            EnemyAi::Ranged { range, cooldown, projectile_speed } => {
+                if distance > range {
+                    continue;
+                }
+                shots.send(FireProjectile {
+                    team: Team::Enemy,
+                    position,
+                    velocity: aim * projectile_speed,
+                    damage: def.damage,
+                    radius: ARROW_RADIUS,
+                    pierce: 0,
+                });
+                ability.cooldown = cooldown;
+            }
+            EnemyAi::Support { radius, heal, cooldown } => {
+                // Only tends to orcs while they fight
+                if distance > def.detection_range {
+                    continue;
+                }
+                heals.extend(grid.within(position, radius).map(|target| (target, heal)));
+                ability.cooldown = cooldown;
+            }
+            EnemyAi::Charge { range, speed, duration, cooldown } => {
+                if distance > range {
+                    continue;
+                }
+                let speed = ENEMY_SPEED * def.speed * speed * difficulty.speed_multiplier();
+                ability.charge(aim, speed, duration, def.damage);
+                ability.cooldown = cooldown;
+            }
+        }
+        ability.attacking = ATTACK_ANIMATION_TIME;
+    }
+
+    for (target, heal) in heals {
+        let Ok((mut enemy, ..)) = enemy_query.get_mut(target) else { continue; };
+        if enemy.health > 0 && enemy.health < enemy.max_health {
+            enemy.health = (enemy.health + heal).min(enemy.max_health);
+        }
+    }
+}
+
+/// Despawns enemies without health left and counts them as kills.
+pub(crate) fn remove_dead_enemies(
+    mut commands: Commands,
+    mut wave: ResMut<WaveState>,
+    mut killed: EventWriter<EnemyKilled>,
+    query: Query<(Entity, &Enemy, &EnemyType, &Transform)>,
+) {
+    for (entity, enemy, enemy_type, transform) in &query {
+        if enemy.health > 0 {
+            continue;
+        }
+        commands.entity(entity).despawn_recursive();
+        wave.kills += 1;
+        killed.send(EnemyKilled { enemy_type: enemy_type.clone(), position: transform.translation.truncate() });
+        debug!(target: ENEMY_TARGET, "{:?} killed", enemy_type);
+    }
+}
+
 fn distance(player: Vec3,enemy: Vec3) -> f32 {
     let distance_x = enemy.x - player.x;
     let distance_y = enemy.y - player.y;
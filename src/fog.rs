@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::render::texture::ImageSampler;
+use crate::enemy::Enemy;
+use crate::player::Player;
+use crate::terrain::{in_vision, tile_world_size, world_to_grid, TerrainGenerated, TerrainMap};
+use crate::weather::WeatherState;
+
+/// Above terrain and vegetation, below the player.
+const FOG_Z: f32 = 0.5;
+const UNEXPLORED_ALPHA: u8 = 255;
+/// Explored tiles outside the vision radius stay dimmed.
+const EXPLORED_ALPHA: u8 = 140;
+
+/// Fog of war over the world: black where nothing has been explored, dimmed
+/// where the player has been, clear within the vision radius. Enemies outside
+/// the vision radius are hidden. Exploration itself lives in `TerrainMap`.
+pub struct FogPlugin;
+
+#[derive(Component)]
+struct Fog;
+
+impl Plugin for FogPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, fog_spawn)
+            .add_systems(Update, (
+                fit_fog_to_map.run_if(on_event::<TerrainGenerated>()),
+                render_fog.run_if(resource_exists::<TerrainMap>),
+                hide_unseen_enemies,
+            ).chain());
+    }
+}
+
+fn fog_spawn(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let mut image = Image::new_fill(
+        Extent3d { width: 1, height: 1, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        &[0, 0, 0, UNEXPLORED_ALPHA],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    // Blends neighbouring tiles so the edge of the vision is soft
+    image.sampler = ImageSampler::linear();
+
+    commands.spawn((
+        SpriteBundle {
+            texture: images.add(image),
+            transform: Transform::from_xyz(0.0, 0.0, FOG_Z),
+            ..default()
+        },
+        Fog,
+    ));
+}
+
+/// Stretches the fog over the whole map, one pixel per tile.
+fn fit_fog_to_map(terrain: Res<TerrainMap>, mut query: Query<(&mut Sprite, &mut Transform), With<Fog>>) {
+    let tile = tile_world_size();
+    let size = Vec2::new(terrain.rows as f32, terrain.columns as f32) * tile;
+    for (mut sprite, mut transform) in &mut query {
+        sprite.custom_size = Some(size);
+        // Tile centres sit on multiples of the tile size, starting at zero
+        transform.translation.x = (size.x - tile.x) / 2.0;
+        transform.translation.y = (size.y - tile.y) / 2.0;
+    }
+}
+
+fn render_fog(
+    terrain: Res<TerrainMap>,
+    weather: Res<WeatherState>,
+    player_query: Query<&Transform, With<Player>>,
+    fog_query: Query<&Handle<Image>, With<Fog>>,
+    mut images: ResMut<Assets<Image>>,
+    mut last_vision: Local<Option<((i32, i32), i32)>>,
+) {
+    let Ok(player) = player_query.get_single() else { return; };
+    let center = world_to_grid(player.translation.truncate());
+    let radius = weather.vision_radius();
+    // Only redraw when the vision moved or the exploration changed
+    if !terrain.is_changed() && *last_vision == Some((center, radius)) {
+        return;
+    }
+    *last_vision = Some((center, radius));
+
+    for handle in &fog_query {
+        let Some(image) = images.get_mut(handle) else { continue; };
+        let (width, height) = (terrain.rows as u32, terrain.columns as u32);
+        if image.width() != width || image.height() != height {
+            image.resize(Extent3d { width, height, depth_or_array_layers: 1 });
+        }
+
+        for x in 0..terrain.rows as i32 {
+            for y in 0..terrain.columns as i32 {
+                let (dx, dy) = (x - center.0, y - center.1);
+                let alpha = if dx * dx + dy * dy <= radius * radius {
+                    0
+                } else if terrain.is_explored(x, y) {
+                    EXPLORED_ALPHA
+                } else {
+                    UNEXPLORED_ALPHA
+                };
+                // Image rows go top to bottom, world y goes up
+                let pixel = ((height - 1 - y as u32) * width + x as u32) as usize * 4;
+                image.data[pixel..pixel + 4].copy_from_slice(&[0, 0, 0, alpha]);
+            }
+        }
+    }
+}
+
+fn hide_unseen_enemies(
+    weather: Res<WeatherState>,
+    player_query: Query<&Transform, With<Player>>,
+    mut enemy_query: Query<(&Transform, &mut Visibility), With<Enemy>>,
+) {
+    let Ok(player) = player_query.get_single() else { return; };
+    let player_position = player.translation.truncate();
+    for (transform, mut visibility) in &mut enemy_query {
+        let visible = in_vision(player_position, transform.translation.truncate(), weather.vision_radius());
+        visibility.set_if_neq(if visible { Visibility::Inherited } else { Visibility::Hidden });
+    }
+}
@@ -0,0 +1,83 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use crate::config::HARVEST_COOLDOWN;
+use crate::inventory::pickup_bundle;
+use crate::logging::TERRAIN_TARGET;
+use crate::player::{Player, PlayerInput};
+use crate::terrain::{tile_world_size, world_to_grid, TerrainGenerated, TerrainMap, VegetationKind, VegetationRemoved};
+use crate::timestep::SimSet;
+
+/// Chopping trees, breaking rocks and picking bushes and flowers next to the
+/// player. Cleared vegetation is a terrain edit, so it stays gone in saves,
+/// and drops its materials as pickups.
+pub struct HarvestPlugin;
+
+/// Hits taken by vegetation that is not cleared yet, by tile.
+#[derive(Resource, Default)]
+struct VegetationDamage(HashMap<(i32, i32), i32>);
+
+impl Plugin for HarvestPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<VegetationDamage>()
+            .add_systems(FixedUpdate, harvest_vegetation
+                .in_set(SimSet::Action)
+                .run_if(resource_exists::<TerrainMap>))
+            .add_systems(Update, forget_damage.run_if(on_event::<TerrainGenerated>()));
+    }
+}
+
+/// Item and count that clearing a piece of vegetation yields.
+pub fn vegetation_yield(kind: VegetationKind) -> (&'static str, u32) {
+    match kind {
+        VegetationKind::Tree => ("wood", 3),
+        VegetationKind::Bush => ("fiber", 2),
+        VegetationKind::Rock => ("stone", 2),
+        VegetationKind::Flower => ("herb", 1),
+    }
+}
+
+fn harvest_vegetation(
+    mut commands: Commands,
+    time: Res<Time>,
+    input: Res<PlayerInput>,
+    mut cooldown: Local<f32>,
+    (mut terrain, mut damage): (ResMut<TerrainMap>, ResMut<VegetationDamage>),
+    player_query: Query<&Transform, With<Player>>,
+    mut removed: EventWriter<VegetationRemoved>,
+) {
+    *cooldown = (*cooldown - time.delta_seconds()).max(0.0);
+    if !input.interact || *cooldown > 0.0 {
+        return;
+    }
+    let Ok(player) = player_query.get_single() else { return; };
+    let position = player.translation.truncate();
+
+    // Closest vegetation on the player's tile or the ones around it
+    let (px, py) = world_to_grid(position);
+    let target = (px - 1..=px + 1)
+        .flat_map(|x| (py - 1..=py + 1).map(move |y| (x, y)))
+        .filter_map(|(x, y)| {
+            let kind = terrain.tile(x, y)?.vegetation_kind()?;
+            let distance = (Vec2::new(x as f32, y as f32) * tile_world_size()).distance_squared(position);
+            Some(((x, y), kind, distance))
+        })
+        .min_by(|a, b| a.2.total_cmp(&b.2));
+    let Some(((x, y), kind, _)) = target else { return; };
+    *cooldown = HARVEST_COOLDOWN;
+
+    let hits = damage.0.entry((x, y)).or_default();
+    *hits += 1;
+    if *hits < kind.max_health() {
+        return;
+    }
+    damage.0.remove(&(x, y));
+    terrain.set_vegetation(x, y, None);
+    let (item, count) = vegetation_yield(kind);
+    commands.spawn(pickup_bundle(item, count, Vec2::new(x as f32, y as f32) * tile_world_size()));
+    removed.send(VegetationRemoved { x, y });
+    debug!(target: TERRAIN_TARGET, "{} at ({}, {}) harvested", kind.label(), x, y);
+}
+
+fn forget_damage(mut damage: ResMut<VegetationDamage>) {
+    damage.0.clear();
+}
@@ -0,0 +1,240 @@
+use bevy::prelude::*;
+use crate::boss::Boss;
+use crate::config::{HUD_BAR_HEIGHT, HUD_BAR_WIDTH, HUD_FONT_SIZE, PLAYER_MAX_STAMINA};
+use crate::daynight::GameClock;
+use crate::enemy::{Enemy, WaveState};
+use crate::leveling::Experience;
+use crate::player::{Player, PlayerStats};
+
+pub struct HudPlugin;
+
+#[derive(Component)]
+struct HealthBar;
+#[derive(Component)]
+struct StaminaBar;
+#[derive(Component)]
+struct XpBar;
+#[derive(Component)]
+struct LevelText;
+#[derive(Component)]
+struct WaveText;
+#[derive(Component)]
+struct KillCounterText;
+#[derive(Component)]
+struct TimerText;
+#[derive(Component)]
+struct ClockText;
+#[derive(Component)]
+struct BossPanel;
+#[derive(Component)]
+struct BossBar;
+#[derive(Component)]
+struct BossNameText;
+
+const HEALTH_COLOR: Color = Color::srgb(0.8, 0.1, 0.1);
+const STAMINA_COLOR: Color = Color::srgb(0.9, 0.75, 0.1);
+const XP_COLOR: Color = Color::srgb(0.3, 0.6, 0.95);
+const BOSS_COLOR: Color = Color::srgb(0.6, 0.05, 0.3);
+const BAR_BACKGROUND: Color = Color::srgba(0.0, 0.0, 0.0, 0.6);
+const BOSS_BAR_WIDTH: f32 = HUD_BAR_WIDTH * 2.5;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostStartup, hud_spawn)
+            .add_systems(Update, (
+                update_health_bar,
+                update_stamina_bar,
+                update_experience,
+                update_wave_text,
+                update_clock_text,
+                update_boss_bar,
+            ));
+    }
+}
+
+fn hud_spawn(mut commands: Commands) {
+    let text_style = TextStyle { font_size: HUD_FONT_SIZE, ..default() };
+
+    // Player bars, top left corner
+    commands.spawn(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(12.0),
+            top: Val::Px(12.0),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(6.0),
+            ..default()
+        },
+        ..default()
+    }).with_children(|parent| {
+        spawn_bar(parent, HEALTH_COLOR, HealthBar);
+        spawn_bar(parent, STAMINA_COLOR, StaminaBar);
+        spawn_bar(parent, XP_COLOR, XpBar);
+        parent.spawn((TextBundle::from_section("Level 1", text_style.clone()), LevelText));
+    });
+
+    // Wave, kills, timer and time of day, top right corner
+    commands.spawn(NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            right: Val::Px(12.0),
+            top: Val::Px(12.0),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::FlexEnd,
+            ..default()
+        },
+        ..default()
+    }).with_children(|parent| {
+        parent.spawn((TextBundle::from_section("Wave 1", text_style.clone()), WaveText));
+        parent.spawn((TextBundle::from_section("Kills 0", text_style.clone()), KillCounterText));
+        parent.spawn((TextBundle::from_section("00:00", text_style.clone()), TimerText));
+        parent.spawn((TextBundle::from_section("Day 1", text_style.clone()), ClockText));
+    });
+
+    // Engaged boss, top centre, hidden outside fights
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                top: Val::Px(12.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        BossPanel,
+    )).with_children(|parent| {
+        parent.spawn((TextBundle::from_section("", text_style), BossNameText));
+        parent.spawn(NodeBundle {
+            style: Style {
+                width: Val::Px(BOSS_BAR_WIDTH),
+                height: Val::Px(HUD_BAR_HEIGHT),
+                ..default()
+            },
+            background_color: BAR_BACKGROUND.into(),
+            ..default()
+        }).with_children(|bar| {
+            bar.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    background_color: BOSS_COLOR.into(),
+                    ..default()
+                },
+                BossBar,
+            ));
+        });
+    });
+}
+
+fn spawn_bar(parent: &mut ChildBuilder, color: Color, marker: impl Component) {
+    parent.spawn(NodeBundle {
+        style: Style {
+            width: Val::Px(HUD_BAR_WIDTH),
+            height: Val::Px(HUD_BAR_HEIGHT),
+            ..default()
+        },
+        background_color: BAR_BACKGROUND.into(),
+        ..default()
+    }).with_children(|bar| {
+        bar.spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                background_color: color.into(),
+                ..default()
+            },
+            marker,
+        ));
+    });
+}
+
+fn update_health_bar(
+    player_query: Query<(&Player, &PlayerStats)>,
+    mut bar_query: Query<&mut Style, With<HealthBar>>,
+) {
+    let Ok((player, stats)) = player_query.get_single() else { return; };
+    for mut style in &mut bar_query {
+        let ratio = player.health.max(0) as f32 / stats.max_health as f32;
+        style.width = Val::Percent(ratio * 100.0);
+    }
+}
+
+fn update_stamina_bar(
+    player_query: Query<&Player>,
+    mut bar_query: Query<&mut Style, With<StaminaBar>>,
+) {
+    let Ok(player) = player_query.get_single() else { return; };
+    for mut style in &mut bar_query {
+        let ratio = player.stamina.max(0.0) / PLAYER_MAX_STAMINA;
+        style.width = Val::Percent(ratio * 100.0);
+    }
+}
+
+fn update_experience(
+    player_query: Query<&Experience>,
+    mut bar_query: Query<&mut Style, With<XpBar>>,
+    mut text_query: Query<&mut Text, With<LevelText>>,
+) {
+    let Ok(experience) = player_query.get_single() else { return; };
+    for mut style in &mut bar_query {
+        style.width = Val::Percent(experience.progress() * 100.0);
+    }
+    for mut text in &mut text_query {
+        text.sections[0].value = format!("Level {}", experience.level);
+    }
+}
+
+fn update_wave_text(
+    wave: Res<WaveState>,
+    mut wave_query: Query<&mut Text, (With<WaveText>, Without<KillCounterText>, Without<TimerText>)>,
+    mut kill_query: Query<&mut Text, (With<KillCounterText>, Without<WaveText>, Without<TimerText>)>,
+    mut timer_query: Query<&mut Text, (With<TimerText>, Without<WaveText>, Without<KillCounterText>)>,
+) {
+    for mut text in &mut wave_query {
+        text.sections[0].value = format!("Wave {}", wave.wave);
+    }
+    for mut text in &mut kill_query {
+        text.sections[0].value = format!("Kills {}", wave.kills);
+    }
+    for mut text in &mut timer_query {
+        let seconds = wave.elapsed.elapsed_secs() as u32;
+        text.sections[0].value = format!("{:02}:{:02}", seconds / 60, seconds % 60);
+    }
+}
+
+fn update_clock_text(clock: Res<GameClock>, mut query: Query<&mut Text, With<ClockText>>) {
+    for mut text in &mut query {
+        text.sections[0].value = clock.label();
+    }
+}
+
+fn update_boss_bar(
+    boss_query: Query<(&Enemy, &Boss)>,
+    mut panel_query: Query<&mut Visibility, With<BossPanel>>,
+    mut bar_query: Query<&mut Style, With<BossBar>>,
+    mut text_query: Query<&mut Text, With<BossNameText>>,
+) {
+    let engaged = boss_query.iter().find(|(_, boss)| boss.engaged);
+    for mut visibility in &mut panel_query {
+        *visibility = if engaged.is_some() { Visibility::Inherited } else { Visibility::Hidden };
+    }
+    let Some((enemy, boss)) = engaged else { return; };
+    for mut style in &mut bar_query {
+        let ratio = enemy.health.max(0) as f32 / boss.max_health as f32;
+        style.width = Val::Percent(ratio * 100.0);
+    }
+    for mut text in &mut text_query {
+        text.sections[0].value = boss.name.clone();
+    }
+}
@@ -0,0 +1,583 @@
+use bevy::prelude::*;
+use bevy::ui::RelativeCursorPosition;
+use bevy::window::PrimaryWindow;
+use serde::{Deserialize, Serialize};
+use crate::assets_loader::load_data;
+use crate::config::{HUD_FONT_SIZE, PICKUP_COLLECT_RADIUS, PICKUP_MAGNET_RADIUS, PICKUP_SPEED, PLAYER_MAX_STAMINA};
+use crate::player::{Player, PlayerInput, PlayerStats};
+use crate::timestep::SimSet;
+use crate::GameStatus;
+
+pub const INVENTORY_SLOTS: usize = 24;
+/// The first slots double as the hotbar, used with the number keys.
+pub const HOTBAR_SLOTS: usize = 6;
+pub const HOTBAR_KEYS: [KeyCode; HOTBAR_SLOTS] = [
+    KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3,
+    KeyCode::Digit4, KeyCode::Digit5, KeyCode::Digit6,
+];
+
+const INVENTORY_KEY: KeyCode = KeyCode::KeyI;
+const INVENTORY_COLUMNS: usize = 6;
+const SLOT_SIZE: f32 = 44.0;
+const ICON_SIZE: f32 = 26.0;
+/// Between vegetation and the fog, so pickups outside the vision stay hidden.
+const PICKUP_Z: f32 = 0.3;
+const PICKUP_SIZE: f32 = 18.0;
+
+const PANEL_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.75);
+const SLOT_COLOR: Color = Color::srgba(0.2, 0.2, 0.2, 0.8);
+const SLOT_HOVER_COLOR: Color = Color::srgba(0.35, 0.35, 0.35, 0.9);
+
+/// Items, the player's inventory and pickups lying in the world. Item
+/// definitions come from `assets/items.ron`.
+#[derive(Default)]
+pub struct InventoryPlugin {
+    pub headless: bool,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ItemDef {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub max_stack: u32,
+    /// Icon colour, until items get sprites.
+    pub color: (f32, f32, f32),
+    #[serde(default)]
+    pub effect: ItemEffect,
+}
+
+/// What using an item does. Items without one are only materials.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum ItemEffect {
+    #[default]
+    None,
+    Heal(i32),
+    RestoreStamina(f32),
+}
+
+#[derive(Resource)]
+pub struct ItemDatabase {
+    items: Vec<ItemDef>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ItemStack {
+    pub item: String,
+    pub count: u32,
+}
+
+#[derive(Component, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Inventory {
+    pub slots: Vec<Option<ItemStack>>,
+}
+
+/// Items on the ground, pulled towards the player once close enough.
+#[derive(Component, Clone, Debug)]
+pub struct Pickup {
+    pub item: String,
+    pub count: u32,
+}
+
+/// Despawns a pickup left on the ground for too long.
+#[derive(Component, Clone, Debug)]
+pub struct PickupLifetime(pub Timer);
+
+/// Whether the inventory window is open. The camera stops panning meanwhile,
+/// since dragging items uses the same mouse buttons.
+#[derive(Resource, Default)]
+pub struct InventoryOpen(pub bool);
+
+/// Slot an item is being dragged from.
+#[derive(Resource, Default)]
+struct Dragging(Option<usize>);
+
+#[derive(Component)]
+struct InventoryWindow;
+#[derive(Component)]
+struct Hotbar;
+#[derive(Component)]
+struct InventorySlot(usize);
+#[derive(Component)]
+struct SlotIcon;
+#[derive(Component)]
+struct SlotCount;
+#[derive(Component)]
+struct ItemTooltip;
+#[derive(Component)]
+struct DragIcon;
+
+impl Plugin for InventoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ItemDatabase::load())
+            .init_resource::<InventoryOpen>()
+            .add_systems(FixedUpdate, (expire_pickups, collect_pickups, move_items, use_items).chain().in_set(SimSet::Action));
+        if !self.headless {
+            app.init_resource::<Dragging>()
+                .add_systems(PostStartup, inventory_spawn)
+                .add_systems(Update, (
+                    attach_pickup_sprite,
+                    toggle_inventory,
+                    drag_items,
+                    update_slots,
+                    update_tooltip,
+                ).chain());
+        }
+    }
+}
+
+impl InventoryPlugin {
+    /// Items and pickups only: no sprites or UI.
+    pub fn headless() -> Self {
+        Self { headless: true }
+    }
+}
+
+impl ItemDatabase {
+    /// Reads the item definitions from `assets/items.ron`.
+    pub fn load() -> Self {
+        let items: Vec<ItemDef> = load_data("items.ron");
+        Self { items }
+    }
+    pub fn get(&self, id: &str) -> Option<&ItemDef> {
+        self.items.iter().find(|item| item.id == id)
+    }
+    pub fn max_stack(&self, id: &str) -> u32 {
+        self.get(id).map_or(1, |item| item.max_stack.max(1))
+    }
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self { slots: vec![None; INVENTORY_SLOTS] }
+    }
+}
+
+impl Inventory {
+    /// Adds to existing stacks first, then to empty slots. Returns what did not fit.
+    pub fn add(&mut self, items: &ItemDatabase, item: &str, mut count: u32) -> u32 {
+        let max_stack = items.max_stack(item);
+        for stack in self.slots.iter_mut().flatten() {
+            if stack.item == item && stack.count < max_stack {
+                let moved = count.min(max_stack - stack.count);
+                stack.count += moved;
+                count -= moved;
+            }
+        }
+        for slot in self.slots.iter_mut() {
+            if count == 0 {
+                break;
+            }
+            if slot.is_none() {
+                let moved = count.min(max_stack);
+                *slot = Some(ItemStack { item: item.to_string(), count: moved });
+                count -= moved;
+            }
+        }
+        count
+    }
+    pub fn count(&self, item: &str) -> u32 {
+        self.slots.iter().flatten()
+            .filter(|stack| stack.item == item)
+            .map(|stack| stack.count)
+            .sum()
+    }
+    pub fn get(&self, slot: usize) -> Option<&ItemStack> {
+        self.slots.get(slot)?.as_ref()
+    }
+    /// Removes one item from `slot`, returning its id.
+    pub fn take_one(&mut self, slot: usize) -> Option<String> {
+        let entry = self.slots.get_mut(slot)?;
+        let stack = entry.as_mut()?;
+        let item = stack.item.clone();
+        stack.count -= 1;
+        if stack.count == 0 {
+            *entry = None;
+        }
+        Some(item)
+    }
+    /// Swaps two slots, or merges them if they hold the same item.
+    pub fn move_slot(&mut self, items: &ItemDatabase, from: usize, to: usize) {
+        if from == to || from >= self.slots.len() || to >= self.slots.len() {
+            return;
+        }
+        if let (Some(source), Some(target)) = (&self.slots[from], &self.slots[to]) {
+            if source.item == target.item {
+                let moved = source.count.min(items.max_stack(&target.item).saturating_sub(target.count));
+                self.slots[to].as_mut().unwrap().count += moved;
+                let source = self.slots[from].as_mut().unwrap();
+                source.count -= moved;
+                if source.count == 0 {
+                    self.slots[from] = None;
+                }
+                return;
+            }
+        }
+        self.slots.swap(from, to);
+    }
+}
+
+pub fn pickup_bundle(item: &str, count: u32, position: Vec2) -> impl Bundle {
+    (
+        // Turned into a diamond so it reads as an item rather than a tile
+        SpatialBundle::from_transform(Transform::from_translation(position.extend(PICKUP_Z))
+            .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4))),
+        Pickup { item: item.to_string(), count },
+    )
+}
+
+fn expire_pickups(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut PickupLifetime)>,
+) {
+    for (entity, mut lifetime) in &mut query {
+        if lifetime.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn collect_pickups(
+    mut commands: Commands,
+    time: Res<Time>,
+    items: Res<ItemDatabase>,
+    mut player_query: Query<(&Transform, &mut Inventory), With<Player>>,
+    mut pickup_query: Query<(Entity, &mut Transform, &mut Pickup), Without<Player>>,
+) {
+    let Ok((player, mut inventory)) = player_query.get_single_mut() else { return; };
+    let player_position = player.translation.truncate();
+
+    for (entity, mut transform, mut pickup) in &mut pickup_query {
+        let offset = player_position - transform.translation.truncate();
+        let distance = offset.length();
+        if distance > PICKUP_MAGNET_RADIUS {
+            continue;
+        }
+        if distance <= PICKUP_COLLECT_RADIUS {
+            let left = inventory.add(&items, &pickup.item, pickup.count);
+            if left == 0 {
+                commands.entity(entity).despawn_recursive();
+            } else {
+                // Inventory full: leave the rest on the ground
+                pickup.count = left;
+            }
+            continue;
+        }
+        let step = (PICKUP_SPEED * time.delta_seconds()).min(distance);
+        transform.translation += (offset / distance * step).extend(0.0);
+    }
+}
+
+fn move_items(
+    items: Res<ItemDatabase>,
+    mut input: ResMut<PlayerInput>,
+    mut player_query: Query<&mut Inventory, With<Player>>,
+) {
+    let Some((from, to)) = input.move_slot.take() else { return; };
+    let Ok(mut inventory) = player_query.get_single_mut() else { return; };
+    inventory.move_slot(&items, from as usize, to as usize);
+}
+
+fn use_items(
+    items: Res<ItemDatabase>,
+    mut input: ResMut<PlayerInput>,
+    mut player_query: Query<(&mut Player, &PlayerStats, &mut Inventory)>,
+) {
+    let Some(slot) = input.use_slot.take() else { return; };
+    let Ok((mut player, stats, mut inventory)) = player_query.get_single_mut() else { return; };
+    let Some(stack) = inventory.get(slot as usize) else { return; };
+    let Some(item) = items.get(&stack.item) else { return; };
+
+    // Nothing to restore: keep the item
+    match item.effect {
+        ItemEffect::None => return,
+        ItemEffect::Heal(_) if player.health >= stats.max_health => return,
+        ItemEffect::RestoreStamina(_) if player.stamina >= PLAYER_MAX_STAMINA => return,
+        ItemEffect::Heal(amount) => player.health = (player.health + amount).min(stats.max_health),
+        ItemEffect::RestoreStamina(amount) => player.stamina = (player.stamina + amount).min(PLAYER_MAX_STAMINA),
+    }
+    inventory.take_one(slot as usize);
+}
+
+fn attach_pickup_sprite(
+    mut commands: Commands,
+    items: Res<ItemDatabase>,
+    query: Query<(Entity, &Pickup), Added<Pickup>>,
+) {
+    for (entity, pickup) in &query {
+        let (r, g, b) = items.get(&pickup.item).map_or((1.0, 1.0, 1.0), |item| item.color);
+        commands.entity(entity).insert((
+            Sprite {
+                color: Color::srgb(r, g, b),
+                custom_size: Some(Vec2::splat(PICKUP_SIZE)),
+                ..default()
+            },
+            Handle::<Image>::default(),
+        ));
+    }
+}
+
+fn inventory_spawn(mut commands: Commands) {
+    // Hotbar, bottom centre
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                bottom: Val::Px(12.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            ..default()
+        },
+        Hotbar,
+    )).with_children(|parent| {
+        parent.spawn(NodeBundle {
+            style: Style {
+                padding: UiRect::all(Val::Px(4.0)),
+                column_gap: Val::Px(4.0),
+                ..default()
+            },
+            background_color: PANEL_COLOR.into(),
+            ..default()
+        }).with_children(|row| {
+            for slot in 0..HOTBAR_SLOTS {
+                spawn_slot(row, slot);
+            }
+        });
+    });
+
+    // Inventory window, hidden until toggled
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            visibility: Visibility::Hidden,
+            z_index: ZIndex::Global(30),
+            ..default()
+        },
+        InventoryWindow,
+    )).with_children(|parent| {
+        parent.spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(10.0)),
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            background_color: PANEL_COLOR.into(),
+            ..default()
+        }).with_children(|panel| {
+            panel.spawn(TextBundle::from_section("Inventory", TextStyle { font_size: HUD_FONT_SIZE * 1.25, ..default() }));
+            panel.spawn(NodeBundle {
+                style: Style {
+                    display: Display::Grid,
+                    grid_template_columns: RepeatedGridTrack::px(INVENTORY_COLUMNS as u16, SLOT_SIZE),
+                    row_gap: Val::Px(4.0),
+                    column_gap: Val::Px(4.0),
+                    ..default()
+                },
+                ..default()
+            }).with_children(|grid| {
+                for slot in 0..INVENTORY_SLOTS {
+                    spawn_slot(grid, slot);
+                }
+            });
+            panel.spawn((TextBundle::from_section("", TextStyle { font_size: HUD_FONT_SIZE, ..default() }), ItemTooltip));
+            panel.spawn(TextBundle::from_section(
+                "Drag to move, right click or 1-6 to use",
+                TextStyle { font_size: HUD_FONT_SIZE * 0.8, color: Color::srgb(0.7, 0.7, 0.7), ..default() },
+            ));
+        });
+    });
+
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Px(ICON_SIZE),
+                height: Val::Px(ICON_SIZE),
+                ..default()
+            },
+            visibility: Visibility::Hidden,
+            z_index: ZIndex::Global(31),
+            ..default()
+        },
+        DragIcon,
+    ));
+}
+
+fn spawn_slot(parent: &mut ChildBuilder, slot: usize) {
+    parent.spawn((
+        NodeBundle {
+            style: Style {
+                width: Val::Px(SLOT_SIZE),
+                height: Val::Px(SLOT_SIZE),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            background_color: SLOT_COLOR.into(),
+            ..default()
+        },
+        InventorySlot(slot),
+        RelativeCursorPosition::default(),
+    )).with_children(|slot_node| {
+        slot_node.spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Px(ICON_SIZE),
+                    height: Val::Px(ICON_SIZE),
+                    ..default()
+                },
+                ..default()
+            },
+            SlotIcon,
+        ));
+        slot_node.spawn((
+            TextBundle::from_section("", TextStyle { font_size: HUD_FONT_SIZE * 0.8, ..default() })
+                .with_style(Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(3.0),
+                    bottom: Val::Px(1.0),
+                    ..default()
+                }),
+            SlotCount,
+        ));
+        if slot < HOTBAR_SLOTS {
+            slot_node.spawn(
+                TextBundle::from_section((slot + 1).to_string(), TextStyle { font_size: HUD_FONT_SIZE * 0.7, ..default() })
+                    .with_style(Style {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(3.0),
+                        top: Val::Px(1.0),
+                        ..default()
+                    }),
+            );
+        }
+    });
+}
+
+fn toggle_inventory(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    state: Res<State<GameStatus>>,
+    mut open: ResMut<InventoryOpen>,
+    mut window_query: Query<&mut Visibility, With<InventoryWindow>>,
+    mut hotbar_query: Query<&mut Visibility, (With<Hotbar>, Without<InventoryWindow>)>,
+) {
+    let in_game = *state.get() == GameStatus::Started;
+    let now_open = in_game && (open.0 != keyboard.just_pressed(INVENTORY_KEY));
+    if open.0 != now_open {
+        open.0 = now_open;
+    }
+    for mut visibility in &mut window_query {
+        visibility.set_if_neq(if now_open { Visibility::Inherited } else { Visibility::Hidden });
+    }
+    for mut visibility in &mut hotbar_query {
+        visibility.set_if_neq(if in_game { Visibility::Inherited } else { Visibility::Hidden });
+    }
+}
+
+/// Left drag moves items between slots and right click uses one. Both go
+/// through `PlayerInput`, so the next tick applies them and replays record them.
+fn drag_items(
+    (mouse, open, items): (Res<ButtonInput<MouseButton>>, Res<InventoryOpen>, Res<ItemDatabase>),
+    mut dragging: ResMut<Dragging>,
+    mut input: ResMut<PlayerInput>,
+    (window_query, ui_scale): (Query<&Window, With<PrimaryWindow>>, Res<UiScale>),
+    slot_query: Query<(&InventorySlot, &RelativeCursorPosition)>,
+    player_query: Query<&Inventory, With<Player>>,
+    mut icon_query: Query<(&mut Style, &mut BackgroundColor, &mut Visibility), With<DragIcon>>,
+) {
+    let Ok(inventory) = player_query.get_single() else { return; };
+    let hovered = slot_query.iter()
+        .find(|(_, cursor)| cursor.mouse_over())
+        .map(|(slot, _)| slot.0);
+
+    if !open.0 {
+        dragging.0 = None;
+    } else if mouse.just_pressed(MouseButton::Left) {
+        dragging.0 = hovered.filter(|slot| inventory.get(*slot).is_some());
+    } else if mouse.just_released(MouseButton::Left) {
+        if let (Some(from), Some(to)) = (dragging.0.take(), hovered) {
+            input.move_slot = Some((from as u8, to as u8));
+        }
+    }
+    if mouse.just_pressed(MouseButton::Right) {
+        if let Some(slot) = hovered {
+            input.use_slot = Some(slot as u8);
+        }
+    }
+
+    let Ok((mut style, mut color, mut visibility)) = icon_query.get_single_mut() else { return; };
+    let cursor = window_query.get_single().ok().and_then(|window| window.cursor_position());
+    let dragged = dragging.0.and_then(|slot| inventory.get(slot)).and_then(|stack| items.get(&stack.item));
+    match (dragged, cursor) {
+        (Some(item), Some(cursor)) => {
+            let (r, g, b) = item.color;
+            color.0 = Color::srgb(r, g, b);
+            let position = cursor / ui_scale.0 - Vec2::splat(ICON_SIZE / 2.0);
+            style.left = Val::Px(position.x);
+            style.top = Val::Px(position.y);
+            visibility.set_if_neq(Visibility::Inherited);
+        }
+        _ => {
+            visibility.set_if_neq(Visibility::Hidden);
+        }
+    }
+}
+
+fn update_slots(
+    items: Res<ItemDatabase>,
+    player_query: Query<&Inventory, With<Player>>,
+    mut slot_query: Query<(&InventorySlot, &RelativeCursorPosition, &mut BackgroundColor, &Children)>,
+    mut icon_query: Query<&mut BackgroundColor, (With<SlotIcon>, Without<InventorySlot>)>,
+    mut count_query: Query<&mut Text, With<SlotCount>>,
+) {
+    let Ok(inventory) = player_query.get_single() else { return; };
+    for (slot, cursor, mut background, children) in &mut slot_query {
+        background.0 = if cursor.mouse_over() { SLOT_HOVER_COLOR } else { SLOT_COLOR };
+        let stack = inventory.get(slot.0);
+        for &child in children {
+            if let Ok(mut icon) = icon_query.get_mut(child) {
+                icon.0 = match stack.and_then(|stack| items.get(&stack.item)) {
+                    Some(ItemDef { color: (r, g, b), .. }) => Color::srgb(*r, *g, *b),
+                    None => Color::NONE,
+                };
+            }
+            if let Ok(mut text) = count_query.get_mut(child) {
+                text.sections[0].value = match stack {
+                    Some(stack) if stack.count > 1 => stack.count.to_string(),
+                    _ => String::new(),
+                };
+            }
+        }
+    }
+}
+
+fn update_tooltip(
+    items: Res<ItemDatabase>,
+    player_query: Query<&Inventory, With<Player>>,
+    slot_query: Query<(&InventorySlot, &RelativeCursorPosition)>,
+    mut tooltip_query: Query<&mut Text, With<ItemTooltip>>,
+) {
+    let Ok(inventory) = player_query.get_single() else { return; };
+    let hovered = slot_query.iter()
+        .find(|(_, cursor)| cursor.mouse_over())
+        .and_then(|(slot, _)| inventory.get(slot.0))
+        .and_then(|stack| items.get(&stack.item));
+    for mut text in &mut tooltip_query {
+        text.sections[0].value = match hovered {
+            Some(item) => format!("{}: {}", item.name, item.description),
+            None => String::new(),
+        };
+    }
+}
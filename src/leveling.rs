@@ -0,0 +1,308 @@
+use bevy::prelude::*;
+use rand::seq::index::sample;
+use serde::{Deserialize, Serialize};
+use crate::assets_loader::load_data;
+use crate::config::{UPGRADE_CHOICES, XP_BASE, XP_GROWTH};
+use crate::enemy::{remove_dead_enemies, EnemyDatabase, EnemyKilled};
+use crate::logging::PLAYER_TARGET;
+use crate::player::{Player, PlayerInput, PlayerStats};
+use crate::rng::GameRng;
+use crate::timestep::SimSet;
+use crate::weapons::{WeaponDatabase, Weapons};
+use crate::GameStatus;
+
+const CHOICE_KEYS: [KeyCode; UPGRADE_CHOICES] = [KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3];
+const OVERLAY_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.7);
+const BUTTON_COLOR: Color = Color::srgb(0.25, 0.2, 0.15);
+const BUTTON_HOVER_COLOR: Color = Color::srgb(0.4, 0.32, 0.2);
+
+/// Experience from kills and level-ups. Every level pauses the game on a pick
+/// of upgrades, rolled from `assets/upgrades.ron` with the game RNG.
+///
+/// The pick reaches the simulation through `PlayerInput::pick_upgrade`, so it
+/// is recorded in replays like any other input.
+#[derive(Default)]
+pub struct LevelingPlugin {
+    pub headless: bool,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct UpgradeDef {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub effect: UpgradeEffect,
+}
+
+/// Stat change from taking an upgrade once. Multiplier bonuses add up.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub enum UpgradeEffect {
+    MaxHealth(i32),
+    MoveSpeed(f32),
+    SprintSpeed(f32),
+    StaminaRegen(f32),
+    AttackDamage(f32),
+    AttackArea(f32),
+    /// Equips the weapon with this id, or levels it up if already carried.
+    Weapon(String),
+}
+
+#[derive(Resource)]
+pub struct UpgradeDatabase {
+    upgrades: Vec<UpgradeDef>,
+}
+
+/// Level and experience towards the next one. Stored in saves.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Experience {
+    pub level: u32,
+    pub xp: u32,
+}
+
+/// Upgrades on offer, as indices into the `UpgradeDatabase`, and level-ups
+/// still waiting for a pick after this one.
+#[derive(Resource, Default, Debug)]
+pub struct UpgradeChoices {
+    pub options: Vec<usize>,
+    pub pending: u32,
+}
+
+#[derive(Component)]
+struct LevelUpMenu;
+#[derive(Component)]
+struct UpgradeButton(u8);
+
+impl Plugin for LevelingPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(UpgradeDatabase::load())
+            .init_resource::<UpgradeChoices>()
+            .add_systems(FixedUpdate, (
+                apply_upgrade.in_set(SimSet::Player),
+                grant_xp.in_set(SimSet::Enemy).after(remove_dead_enemies),
+            ));
+        if !self.headless {
+            app.add_systems(OnEnter(GameStatus::LevelUp), spawn_level_up_menu)
+                .add_systems(OnExit(GameStatus::LevelUp), despawn_level_up_menu)
+                .add_systems(Update, pick_upgrade.run_if(in_state(GameStatus::LevelUp)));
+        }
+    }
+}
+
+impl LevelingPlugin {
+    /// Experience and upgrades only: no level-up screen.
+    pub fn headless() -> Self {
+        Self { headless: true }
+    }
+}
+
+impl UpgradeDatabase {
+    /// Reads the upgrade definitions from `assets/upgrades.ron`.
+    pub fn load() -> Self {
+        let upgrades: Vec<UpgradeDef> = load_data("upgrades.ron");
+        Self { upgrades }
+    }
+    pub fn get(&self, index: usize) -> Option<&UpgradeDef> {
+        self.upgrades.get(index)
+    }
+    pub fn len(&self) -> usize {
+        self.upgrades.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.upgrades.is_empty()
+    }
+}
+
+impl UpgradeEffect {
+    pub fn apply(&self, stats: &mut PlayerStats, player: &mut Player, weapons: &mut Weapons, database: &WeaponDatabase) {
+        match self {
+            UpgradeEffect::MaxHealth(amount) => {
+                stats.max_health += amount;
+                player.health += amount;
+            }
+            UpgradeEffect::MoveSpeed(bonus) => stats.move_speed += bonus,
+            UpgradeEffect::SprintSpeed(bonus) => stats.sprint_speed += bonus,
+            UpgradeEffect::StaminaRegen(bonus) => stats.stamina_regen += bonus,
+            UpgradeEffect::AttackDamage(bonus) => stats.attack_damage += bonus,
+            UpgradeEffect::AttackArea(bonus) => stats.attack_area += bonus,
+            UpgradeEffect::Weapon(id) => weapons.upgrade(database, id),
+        }
+    }
+    /// Stat upgrades always apply; weapon ones only while the weapon can level up or fit.
+    pub fn available(&self, weapons: &Weapons, database: &WeaponDatabase) -> bool {
+        match self {
+            UpgradeEffect::Weapon(id) => weapons.can_upgrade(database, id),
+            _ => true,
+        }
+    }
+}
+
+impl Default for Experience {
+    fn default() -> Self {
+        Self { level: 1, xp: 0 }
+    }
+}
+
+impl Experience {
+    /// Experience needed to go from `level` to the next.
+    pub fn xp_to_next(level: u32) -> u32 {
+        (XP_BASE * (level as f32).powf(XP_GROWTH)) as u32
+    }
+    /// Adds experience and returns how many levels were gained.
+    pub fn add(&mut self, xp: u32) -> u32 {
+        self.xp += xp;
+        let mut levels = 0;
+        while self.xp >= Self::xp_to_next(self.level) {
+            self.xp -= Self::xp_to_next(self.level);
+            self.level += 1;
+            levels += 1;
+        }
+        levels
+    }
+    /// Progress towards the next level, 0 to 1.
+    pub fn progress(&self) -> f32 {
+        self.xp as f32 / Self::xp_to_next(self.level) as f32
+    }
+}
+
+impl UpgradeChoices {
+    /// Rolls a new set of distinct upgrades to pick from, among those that
+    /// would still change something.
+    fn offer(&mut self, upgrades: &UpgradeDatabase, rng: &mut GameRng, weapons: &Weapons, database: &WeaponDatabase) {
+        let available: Vec<usize> = (0..upgrades.len())
+            .filter(|index| upgrades.upgrades[*index].effect.available(weapons, database))
+            .collect();
+        let amount = UPGRADE_CHOICES.min(available.len());
+        self.options = sample(&mut **rng, available.len(), amount).iter()
+            .map(|index| available[index])
+            .collect();
+    }
+}
+
+fn grant_xp(
+    mut killed: EventReader<EnemyKilled>,
+    (upgrades, weapons, enemies): (Res<UpgradeDatabase>, Res<WeaponDatabase>, Res<EnemyDatabase>),
+    mut choices: ResMut<UpgradeChoices>,
+    mut rng: ResMut<GameRng>,
+    mut next_state: ResMut<NextState<GameStatus>>,
+    mut player_query: Query<(&mut Experience, &Weapons)>,
+) {
+    let Ok((mut experience, equipped)) = player_query.get_single_mut() else { return; };
+    let mut levels = 0;
+    for event in killed.read() {
+        levels += experience.add(enemies.get(&event.enemy_type).map_or(0, |def| def.xp));
+    }
+    if levels == 0 || upgrades.is_empty() {
+        return;
+    }
+    info!(target: PLAYER_TARGET, "Reached level {}", experience.level);
+    choices.pending += levels;
+    if choices.options.is_empty() {
+        choices.pending -= 1;
+        choices.offer(&upgrades, &mut rng, equipped, &weapons);
+        next_state.set(GameStatus::LevelUp);
+    }
+}
+
+fn apply_upgrade(
+    mut input: ResMut<PlayerInput>,
+    (upgrades, weapons): (Res<UpgradeDatabase>, Res<WeaponDatabase>),
+    mut choices: ResMut<UpgradeChoices>,
+    mut rng: ResMut<GameRng>,
+    mut next_state: ResMut<NextState<GameStatus>>,
+    mut player_query: Query<(&mut Player, &mut PlayerStats, &mut Weapons)>,
+) {
+    let Some(pick) = input.pick_upgrade.take() else { return; };
+    let Some(upgrade) = choices.options.get(pick as usize).and_then(|index| upgrades.get(*index)) else { return; };
+    let Ok((mut player, mut stats, mut equipped)) = player_query.get_single_mut() else { return; };
+
+    upgrade.effect.apply(&mut stats, &mut player, &mut equipped, &weapons);
+    debug!(target: PLAYER_TARGET, "Took upgrade {}", upgrade.id);
+    choices.options.clear();
+    // Several levels at once are picked one after the other
+    if choices.pending > 0 {
+        choices.pending -= 1;
+        choices.offer(&upgrades, &mut rng, &equipped, &weapons);
+        next_state.set(GameStatus::LevelUp);
+    }
+}
+
+fn spawn_level_up_menu(
+    mut commands: Commands,
+    upgrades: Res<UpgradeDatabase>,
+    choices: Res<UpgradeChoices>,
+    player_query: Query<&Experience>,
+) {
+    let level = player_query.get_single().map_or(1, |experience| experience.level);
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(12.0),
+                ..default()
+            },
+            background_color: OVERLAY_COLOR.into(),
+            z_index: ZIndex::Global(50),
+            ..default()
+        },
+        LevelUpMenu,
+    )).with_children(|parent| {
+        parent.spawn(TextBundle::from_section(format!("Level {}!", level), TextStyle { font_size: 36.0, ..default() }));
+        parent.spawn(TextBundle::from_section("Choose an upgrade", TextStyle { font_size: 20.0, ..default() }));
+        for (choice, upgrade) in choices.options.iter().filter_map(|index| upgrades.get(*index)).enumerate() {
+            parent.spawn((
+                ButtonBundle {
+                    style: Style {
+                        width: Val::Px(360.0),
+                        flex_direction: FlexDirection::Column,
+                        padding: UiRect::axes(Val::Px(16.0), Val::Px(8.0)),
+                        ..default()
+                    },
+                    background_color: BUTTON_COLOR.into(),
+                    ..default()
+                },
+                UpgradeButton(choice as u8),
+            )).with_children(|button| {
+                button.spawn(TextBundle::from_section(
+                    format!("{}. {}", choice + 1, upgrade.name),
+                    TextStyle { font_size: 22.0, ..default() },
+                ));
+                button.spawn(TextBundle::from_section(
+                    upgrade.description.clone(),
+                    TextStyle { font_size: 16.0, color: Color::srgb(0.8, 0.8, 0.8), ..default() },
+                ));
+            });
+        }
+    });
+}
+
+fn despawn_level_up_menu(mut commands: Commands, query: Query<Entity, With<LevelUpMenu>>) {
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn pick_upgrade(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    choices: Res<UpgradeChoices>,
+    mut input: ResMut<PlayerInput>,
+    mut next_state: ResMut<NextState<GameStatus>>,
+    mut button_query: Query<(&Interaction, &UpgradeButton, &mut BackgroundColor), Changed<Interaction>>,
+) {
+    let mut pick = CHOICE_KEYS.iter().position(|key| keyboard.just_pressed(*key)).map(|choice| choice as u8);
+    for (interaction, button, mut color) in &mut button_query {
+        match interaction {
+            Interaction::Pressed => pick = Some(button.0),
+            Interaction::Hovered => *color = BUTTON_HOVER_COLOR.into(),
+            Interaction::None => *color = BUTTON_COLOR.into(),
+        }
+    }
+    if let Some(pick) = pick.filter(|pick| (*pick as usize) < choices.options.len()) {
+        input.pick_upgrade = Some(pick);
+        next_state.set(GameStatus::Started);
+    }
+}
@@ -0,0 +1,89 @@
+pub mod player;
+pub mod config;
+pub mod daynight;
+pub mod terrain;
+pub mod enemy;
+pub mod fog;
+pub mod harvest;
+pub mod inventory;
+pub mod leveling;
+pub mod assets_loader;
+pub mod boss;
+pub mod camera;
+pub mod combat;
+pub mod hud;
+pub mod logging;
+pub mod loot;
+pub mod menu;
+pub mod minimap;
+pub mod projectile;
+pub mod replay;
+pub mod rng;
+pub mod save;
+pub mod settings;
+pub mod spatial;
+pub mod timestep;
+pub mod weather;
+pub mod weapons;
+#[cfg(feature = "dev")]
+pub mod debug;
+#[cfg(feature = "dev")]
+pub mod cheats;
+
+use bevy::app::PluginGroupBuilder;
+use bevy::prelude::*;
+use bevy::state::app::StatesPlugin;
+use crate::boss::BossPlugin;
+use crate::combat::CombatPlugin;
+use crate::daynight::DayNightPlugin;
+use crate::enemy::EnemyPlugin;
+use crate::harvest::HarvestPlugin;
+use crate::inventory::InventoryPlugin;
+use crate::leveling::LevelingPlugin;
+use crate::loot::LootPlugin;
+use crate::player::PlayerPlugin;
+use crate::projectile::ProjectilePlugin;
+use crate::rng::RngPlugin;
+use crate::terrain::TerrainPlugin;
+use crate::timestep::TimestepPlugin;
+use crate::weapons::WeaponsPlugin;
+use crate::weather::WeatherPlugin;
+
+#[derive(Component,Deref,DerefMut)]
+struct AnimationTimer(Timer);
+#[derive(Default,Clone,Copy,Debug,Eq,PartialEq,Hash,States)]
+pub enum GameStatus{
+    #[default]
+    Started,
+    Paused,
+    MainMenu,
+    /// Waiting for the player to pick an upgrade.
+    LevelUp,
+}
+
+/// Game logic (clock, weather, terrain generation, player movement, harvesting, items, loot, experience,
+/// weapons, projectiles, enemy spawning and AI, boss fights) without sprites, UI or a window. Runs on top of `MinimalPlugins`.
+/// Insert a `WorldSeed` before adding the group to get a reproducible run.
+pub struct SimulationPlugins;
+
+impl PluginGroup for SimulationPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(StatesPlugin)
+            .add(RngPlugin)
+            .add(TimestepPlugin::default())
+            .add(DayNightPlugin::headless())
+            .add(WeatherPlugin::headless())
+            .add(TerrainPlugin::headless())
+            .add(PlayerPlugin::headless())
+            .add(EnemyPlugin::headless())
+            .add(HarvestPlugin)
+            .add(InventoryPlugin::headless())
+            .add(LootPlugin)
+            .add(LevelingPlugin::headless())
+            .add(CombatPlugin)
+            .add(ProjectilePlugin::headless())
+            .add(WeaponsPlugin::headless())
+            .add(BossPlugin::headless())
+    }
+}
@@ -0,0 +1,58 @@
+use std::path::PathBuf;
+use bevy::log::tracing_subscriber::{fmt, Layer};
+use bevy::log::{BoxedLayer, Level, LogPlugin};
+use bevy::prelude::*;
+use tracing_appender::non_blocking::WorkerGuard;
+
+// Per-module log targets, so a filter like `enemy=debug` only touches one subsystem
+pub const ASSETS_TARGET: &str = "assets";
+pub const TERRAIN_TARGET: &str = "terrain";
+pub const PLAYER_TARGET: &str = "player";
+pub const ENEMY_TARGET: &str = "enemy";
+pub const REPLAY_TARGET: &str = "replay";
+pub const SAVE_TARGET: &str = "save";
+pub const SETTINGS_TARGET: &str = "settings";
+pub const WEATHER_TARGET: &str = "weather";
+
+const DEFAULT_FILTER: &str = "wgpu=error,naga=warn,assets=info,terrain=info,player=info,enemy=info,replay=info,save=info,settings=info,weather=info";
+const LOG_FILE_ARG: &str = "--log-file";
+const LOG_FILE_ENV: &str = "ORCWARS_LOG_FILE";
+const LOG_FILE_PREFIX: &str = "orcwars.log";
+
+/// Keeps the background log writer alive; dropping it flushes and closes the file.
+#[derive(Resource)]
+struct LogFileGuard(#[allow(dead_code)] WorkerGuard);
+
+/// `LogPlugin` with the game's targets. `RUST_LOG` still overrides the filter.
+/// Passing `--log-file` (or setting `ORCWARS_LOG_FILE`) also writes a daily
+/// rolling log into the user data dir, which testers can attach to bug reports.
+pub fn log_plugin() -> LogPlugin {
+    LogPlugin {
+        filter: DEFAULT_FILTER.to_string(),
+        level: Level::INFO,
+        custom_layer: file_layer,
+    }
+}
+
+pub fn log_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("OrcWars").join("logs"))
+}
+
+fn file_layer(app: &mut App) -> Option<BoxedLayer> {
+    let enabled = std::env::args().any(|arg| arg == LOG_FILE_ARG)
+        || std::env::var_os(LOG_FILE_ENV).is_some();
+    if !enabled {
+        return None;
+    }
+
+    let dir = log_dir()?;
+    if let Err(err) = std::fs::create_dir_all(&dir) {
+        eprintln!("Could not create log directory {}: {}", dir.display(), err);
+        return None;
+    }
+    let appender = tracing_appender::rolling::daily(dir, LOG_FILE_PREFIX);
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+    app.insert_resource(LogFileGuard(guard));
+
+    Some(fmt::layer().with_writer(writer).with_ansi(false).boxed())
+}
@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use rand::Rng;
+use serde::Deserialize;
+use crate::assets_loader::load_data;
+use crate::config::LOOT_LIFETIME;
+use crate::enemy::{remove_dead_enemies, EnemyDatabase, EnemyKilled};
+use crate::inventory::{pickup_bundle, PickupLifetime};
+use crate::rng::GameRng;
+use crate::timestep::SimSet;
+
+/// How far drops from one kill scatter around the body.
+pub(crate) const LOOT_SCATTER: f32 = 40.0;
+
+/// Pickups dropped by killed enemies, rolled from the tables in
+/// `assets/loot.ron` with the game RNG so a seed always drops the same loot.
+pub struct LootPlugin;
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct LootDrop {
+    pub item: String,
+    pub min: u32,
+    pub max: u32,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct LootTable {
+    /// Weighted picks per kill.
+    pub rolls: u32,
+    pub weighted: Vec<(u32, Option<LootDrop>)>,
+    #[serde(default)]
+    pub guaranteed: Vec<LootDrop>,
+}
+
+#[derive(Resource, Deref)]
+pub struct LootTables(HashMap<String, LootTable>);
+
+impl Plugin for LootPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(LootTables::load())
+            .add_systems(FixedUpdate, drop_loot
+                .in_set(SimSet::Enemy)
+                .after(remove_dead_enemies));
+    }
+}
+
+impl LootTables {
+    /// Reads the loot tables from `assets/loot.ron`.
+    pub fn load() -> Self {
+        Self(load_data("loot.ron"))
+    }
+}
+
+impl LootTable {
+    /// Items and counts for one kill, guaranteed drops first.
+    pub fn roll(&self, rng: &mut GameRng) -> Vec<(String, u32)> {
+        let mut drops: Vec<(String, u32)> = self.guaranteed.iter()
+            .map(|drop| (drop.item.clone(), drop.roll_count(rng)))
+            .collect();
+        let total: u32 = self.weighted.iter().map(|(weight, _)| weight).sum();
+        if total == 0 {
+            return drops;
+        }
+        for _ in 0..self.rolls {
+            let mut pick = rng.gen_range(0..total);
+            let entry = self.weighted.iter().find(|(weight, _)| {
+                if pick < *weight {
+                    return true;
+                }
+                pick -= weight;
+                false
+            });
+            if let Some((_, Some(drop))) = entry {
+                drops.push((drop.item.clone(), drop.roll_count(rng)));
+            }
+        }
+        drops
+    }
+}
+
+impl LootDrop {
+    pub(crate) fn roll_count(&self, rng: &mut GameRng) -> u32 {
+        rng.gen_range(self.min..=self.max.max(self.min))
+    }
+}
+
+pub(crate) fn drop_loot(
+    mut commands: Commands,
+    mut killed: EventReader<EnemyKilled>,
+    (tables, enemies): (Res<LootTables>, Res<EnemyDatabase>),
+    mut rng: ResMut<GameRng>,
+) {
+    for event in killed.read() {
+        let Some(table) = enemies.get(&event.enemy_type)
+            .and_then(|def| def.loot.as_ref())
+            .and_then(|loot| tables.get(loot)) else { continue; };
+        for (item, count) in table.roll(&mut rng) {
+            let offset = Vec2::new(rng.gen_range(-LOOT_SCATTER..LOOT_SCATTER), rng.gen_range(-LOOT_SCATTER..LOOT_SCATTER));
+            commands.spawn((
+                pickup_bundle(&item, count, event.position + offset),
+                PickupLifetime(Timer::from_seconds(LOOT_LIFETIME, TimerMode::Once)),
+            ));
+        }
+    }
+}
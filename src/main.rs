@@ -1,54 +1,134 @@
-mod player;
-mod config;
-mod terrain;
-mod enemy;
-mod assets_loader;
-
+use std::path::PathBuf;
+use std::time::Duration;
 use::bevy::prelude::*;
-use bevy_inspector_egui::quick::WorldInspectorPlugin;
-use crate::player::PlayerPlugin;
-use bevy_pancam::{PanCam, PanCamPlugin};
-use crate::assets_loader::AssetsLoaderPlugin;
-use crate::config::{HEIGHT, WIDTH};
-use crate::enemy::EnemyPlugin;
-use crate::terrain::TerrainPlugin;
-
-#[derive(Component,Deref,DerefMut)]
-struct AnimationTimer(Timer);
-#[derive(Default,Clone,Copy,Debug,Eq,PartialEq,Hash,States)]
-enum GameStatus{
-    #[default]
-    Started,
-    Paused,
+use bevy::app::ScheduleRunnerPlugin;
+use bevy::time::TimeUpdateStrategy;
+use bevy_pancam::PanCamPlugin;
+use orc_wars::assets_loader::AssetsLoaderPlugin;
+use orc_wars::boss::BossPlugin;
+use orc_wars::camera::CameraPlugin;
+use orc_wars::config::TICK_RATE;
+use orc_wars::combat::CombatPlugin;
+use orc_wars::daynight::DayNightPlugin;
+use orc_wars::enemy::EnemyPlugin;
+use orc_wars::fog::FogPlugin;
+use orc_wars::harvest::HarvestPlugin;
+use orc_wars::hud::HudPlugin;
+use orc_wars::inventory::InventoryPlugin;
+use orc_wars::leveling::LevelingPlugin;
+use orc_wars::loot::LootPlugin;
+use orc_wars::logging;
+use orc_wars::menu::MenuPlugin;
+use orc_wars::minimap::MinimapPlugin;
+use orc_wars::player::PlayerPlugin;
+use orc_wars::projectile::ProjectilePlugin;
+use orc_wars::replay::{last_replay_path, Replay, ReplayMode, ReplayPlugin};
+use orc_wars::rng::RngPlugin;
+use orc_wars::save::SavePlugin;
+use orc_wars::settings::{Settings, SettingsPlugin};
+use orc_wars::terrain::TerrainPlugin;
+use orc_wars::timestep::{InterpolationPlugin, TimestepPlugin};
+use orc_wars::weapons::WeaponsPlugin;
+use orc_wars::weather::WeatherPlugin;
+use orc_wars::{GameStatus, SimulationPlugins};
+
+/// Command line flags:
+///   --replay <file>   drive the game from a recorded input stream
+///   --record <file>   where to write this session's replay (default: data dir)
+///   --headless        no window; run the simulation as fast as possible (CI replays)
+///   --log-file        also write a rolling log file (see `logging`)
+#[derive(Default)]
+struct CliArgs {
+    replay: Option<PathBuf>,
+    record: Option<PathBuf>,
+    headless: bool,
 }
-fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins
-            .set(ImagePlugin::default_nearest())
-            .set(WindowPlugin{
-                primary_window: Some(Window{
-                    title: "Orc Wars!".to_string(),
-                    resizable:true,
-                    focused:true,
-                    resolution:(WIDTH,HEIGHT).into(),
-                    ..default()
-                }),
-                ..default()
-            })
-        )
-        .add_plugins(PanCamPlugin::default())
-        .add_plugins(AssetsLoaderPlugin)
-        .add_plugins(TerrainPlugin)
-        .add_plugins(PlayerPlugin)
-        .add_plugins(EnemyPlugin)
-        .add_plugins(WorldInspectorPlugin::new())
-        .add_systems(PostStartup,spawn_camera)
-        .run();
+
+impl CliArgs {
+    fn parse() -> Self {
+        let mut cli = Self::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--replay" => cli.replay = args.next().map(PathBuf::from),
+                "--record" => cli.record = args.next().map(PathBuf::from),
+                "--headless" => cli.headless = true,
+                _ => {}
+            }
+        }
+        cli
+    }
 }
 
-fn spawn_camera(mut commands: Commands) {
-    commands
-        .spawn(Camera2dBundle::default())
-        .insert(PanCam::default())
-    ;
-}
\ No newline at end of file
+fn main() -> AppExit {
+    let cli = CliArgs::parse();
+    let replay = cli.replay.as_ref().map(|path| {
+        Replay::load(path).unwrap_or_else(|err| panic!("Could not load replay {}: {}", path.display(), err))
+    });
+    let tick_rate = replay.as_ref().map_or(TICK_RATE, |replay| replay.tick_rate);
+
+    let mut app = App::new();
+    if let Some(replay) = &replay {
+        app.insert_resource(replay.seed);
+    }
+
+    if cli.headless {
+        app.add_plugins(MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::ZERO)))
+            .add_plugins(logging::log_plugin())
+            .add_plugins(SimulationPlugins.set(TimestepPlugin { tick_rate }))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(1.0 / tick_rate)));
+    } else {
+        let settings = Settings::load();
+        let mut window = Window{
+            title: "Orc Wars!".to_string(),
+            resizable:true,
+            focused:true,
+            ..default()
+        };
+        settings.apply_to_window(&mut window);
+
+        app
+            .insert_resource(settings)
+            .add_plugins(DefaultPlugins
+                .set(ImagePlugin::default_nearest())
+                .set(logging::log_plugin())
+                .set(WindowPlugin{
+                    primary_window: Some(window),
+                    ..default()
+                })
+            )
+            .add_plugins((PanCamPlugin::default(), CameraPlugin))
+            .add_plugins(AssetsLoaderPlugin)
+            .add_plugins((RngPlugin, TimestepPlugin { tick_rate }, InterpolationPlugin))
+            .add_plugins((DayNightPlugin::default(), WeatherPlugin::default()))
+            .add_plugins(TerrainPlugin::default())
+            .add_plugins(PlayerPlugin::default())
+            .add_plugins(EnemyPlugin::default())
+            .add_plugins((HarvestPlugin, InventoryPlugin::default(), LootPlugin, LevelingPlugin::default()))
+            .add_plugins((CombatPlugin, ProjectilePlugin::default(), WeaponsPlugin::default(), BossPlugin::default()))
+            .add_plugins((HudPlugin, MinimapPlugin, FogPlugin))
+            .add_plugins(SavePlugin::default())
+            .add_plugins((MenuPlugin, SettingsPlugin));
+
+        // Replays start straight from the recorded world instead of the title screen
+        if replay.is_none() {
+            app.insert_state(GameStatus::MainMenu);
+        }
+
+        #[cfg(feature = "dev")]
+        app.add_plugins((orc_wars::debug::DebugPlugin, orc_wars::cheats::CheatsPlugin));
+    }
+
+    match replay {
+        Some(replay) => {
+            app.add_plugins(ReplayPlugin { mode: ReplayMode::Playback(replay) });
+        }
+        None => {
+            if let Some(path) = cli.record.or_else(last_replay_path) {
+                app.add_plugins(ReplayPlugin { mode: ReplayMode::Record(path) });
+            }
+        }
+    }
+
+    app.run()
+}
@@ -0,0 +1,446 @@
+use bevy::app::AppExit;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use crate::daynight::GameClock;
+use crate::enemy::{Difficulty, Enemy, WaveState};
+use crate::inventory::{Inventory, Pickup};
+use crate::leveling::{Experience, UpgradeChoices};
+use crate::player::{Player, PlayerStats, PLAYER_SPAWN_POSITION};
+use crate::projectile::{Projectile, ProjectilePool};
+use crate::rng::{GameRng, WorldSeed};
+use crate::save::{LoadGameEvent, SaveDir};
+use crate::settings::{SettingKind, Settings};
+use crate::terrain::{generate_terrain, replace_terrain, MapSize};
+use crate::timestep::Interpolated;
+use crate::weapons::Weapons;
+use crate::weather::WeatherState;
+use crate::GameStatus;
+
+/// Title screen shown on launch and from the pause menu.
+pub struct MenuPlugin;
+
+#[derive(SubStates, Default, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[source(GameStatus = GameStatus::MainMenu)]
+pub enum MenuScreen {
+    #[default]
+    Main,
+    NewGame,
+    Settings,
+    Credits,
+}
+
+/// Starts a fresh world. The current one is thrown away.
+#[derive(Event)]
+pub struct NewGameEvent {
+    pub seed: WorldSeed,
+    pub map_size: MapSize,
+    pub difficulty: Difficulty,
+}
+
+/// What a menu button does when pressed. Adjustable entries carry a step:
+/// +1 when activated or moved right, -1 when moved left.
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuAction {
+    NewGame,
+    Continue,
+    Settings,
+    Credits,
+    Quit,
+    Back,
+    RandomSeed,
+    MapSize(i32),
+    Start,
+    Setting(SettingKind, i32),
+}
+
+#[derive(Component)]
+struct MenuButton {
+    order: usize,
+    action: MenuAction,
+}
+#[derive(Component)]
+struct SeedText;
+#[derive(Component)]
+struct MapSizeText;
+
+/// Index of the button keyboard and gamepad input act on.
+#[derive(Resource, Default)]
+pub(crate) struct MenuFocus(pub usize);
+
+/// Set while a screen reads raw keyboard input itself (e.g. rebinding a key),
+/// so keyboard navigation stays out of the way.
+#[derive(Resource, Default)]
+pub(crate) struct MenuCapture(pub bool);
+
+#[derive(Resource, Default)]
+struct NewGameOptions {
+    seed: String,
+    map_size: MapSize,
+}
+
+const MENU_BACKGROUND: Color = Color::srgb(0.08, 0.06, 0.05);
+const BUTTON_COLOR: Color = Color::srgb(0.25, 0.2, 0.15);
+const BUTTON_FOCUS_COLOR: Color = Color::srgb(0.55, 0.4, 0.2);
+pub(crate) const TITLE_FONT_SIZE: f32 = 56.0;
+const MENU_FONT_SIZE: f32 = 24.0;
+const MAX_SEED_DIGITS: usize = 19;
+const LICENSE: &str = include_str!("../assets/License.txt");
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_sub_state::<MenuScreen>()
+            .enable_state_scoped_entities::<MenuScreen>()
+            .add_event::<MenuAction>()
+            .add_event::<NewGameEvent>()
+            .init_resource::<MenuFocus>()
+            .init_resource::<MenuCapture>()
+            .init_resource::<NewGameOptions>()
+            .add_systems(OnEnter(MenuScreen::Main), spawn_main_screen)
+            .add_systems(OnEnter(MenuScreen::NewGame), spawn_new_game_screen)
+            .add_systems(OnEnter(MenuScreen::Credits), spawn_credits_screen)
+            .add_systems(Update, (
+                (
+                    keyboard_navigation,
+                    gamepad_navigation,
+                    mouse_navigation,
+                    seed_input.run_if(in_state(MenuScreen::NewGame)),
+                    handle_menu_actions,
+                    highlight_focused,
+                    update_new_game_text.run_if(in_state(MenuScreen::NewGame)),
+                ).chain().run_if(in_state(GameStatus::MainMenu)),
+                start_new_game.run_if(on_event::<NewGameEvent>()),
+            ));
+    }
+}
+
+pub(crate) fn menu_root(screen: MenuScreen) -> impl Bundle {
+    (
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(12.0),
+                ..default()
+            },
+            background_color: MENU_BACKGROUND.into(),
+            z_index: ZIndex::Global(60),
+            ..default()
+        },
+        StateScoped(screen),
+    )
+}
+
+pub(crate) fn menu_text(text: impl Into<String>, font_size: f32) -> TextBundle {
+    TextBundle::from_section(text, TextStyle { font_size, ..default() })
+}
+
+pub(crate) fn spawn_button(parent: &mut ChildBuilder, order: usize, label: impl Into<String>, action: MenuAction, marker: impl Bundle) {
+    parent.spawn((
+        ButtonBundle {
+            style: Style {
+                width: Val::Px(320.0),
+                padding: UiRect::axes(Val::Px(16.0), Val::Px(8.0)),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            background_color: BUTTON_COLOR.into(),
+            ..default()
+        },
+        MenuButton { order, action },
+    )).with_children(|button| {
+        button.spawn((menu_text(label, MENU_FONT_SIZE), marker));
+    });
+}
+
+fn spawn_main_screen(mut commands: Commands, mut focus: ResMut<MenuFocus>, save_dir: Option<Res<SaveDir>>) {
+    focus.0 = 0;
+    let can_continue = save_dir.is_some_and(|dir| dir.latest().is_some());
+
+    commands.spawn(menu_root(MenuScreen::Main)).with_children(|parent| {
+        parent.spawn(menu_text("Orc Wars!", TITLE_FONT_SIZE));
+        let mut buttons = vec![("New Game", MenuAction::NewGame)];
+        if can_continue {
+            buttons.push(("Continue", MenuAction::Continue));
+        }
+        buttons.extend([
+            ("Settings", MenuAction::Settings),
+            ("Credits", MenuAction::Credits),
+            ("Quit", MenuAction::Quit),
+        ]);
+        for (order, (label, action)) in buttons.into_iter().enumerate() {
+            spawn_button(parent, order, label, action, ());
+        }
+    });
+}
+
+fn spawn_new_game_screen(mut commands: Commands, mut focus: ResMut<MenuFocus>, options: Res<NewGameOptions>) {
+    focus.0 = 0;
+    commands.spawn(menu_root(MenuScreen::NewGame)).with_children(|parent| {
+        parent.spawn(menu_text("New Game", TITLE_FONT_SIZE));
+        parent.spawn((menu_text(seed_label(&options), MENU_FONT_SIZE), SeedText));
+        parent.spawn(menu_text("Type digits for a seed, Backspace to clear", MENU_FONT_SIZE * 0.6));
+        spawn_button(parent, 0, "Random seed", MenuAction::RandomSeed, ());
+        spawn_button(parent, 1, map_size_label(&options), MenuAction::MapSize(1), MapSizeText);
+        spawn_button(parent, 2, "Start", MenuAction::Start, ());
+        spawn_button(parent, 3, "Back", MenuAction::Back, ());
+    });
+}
+
+fn spawn_credits_screen(mut commands: Commands, mut focus: ResMut<MenuFocus>) {
+    focus.0 = 0;
+    commands.spawn(menu_root(MenuScreen::Credits)).with_children(|parent| {
+        parent.spawn(menu_text("Credits", TITLE_FONT_SIZE));
+        parent.spawn(menu_text("Player, orc, terrain, water and nature sprites by CraftPix.net", MENU_FONT_SIZE));
+        parent.spawn(menu_text(format!("Licence: {}", LICENSE.trim()), MENU_FONT_SIZE * 0.75));
+        parent.spawn(menu_text("Made with Bevy", MENU_FONT_SIZE));
+        spawn_button(parent, 0, "Back", MenuAction::Back, ());
+    });
+}
+
+fn seed_label(options: &NewGameOptions) -> String {
+    if options.seed.is_empty() {
+        "Seed: random".to_string()
+    } else {
+        format!("Seed: {}", options.seed)
+    }
+}
+
+fn map_size_label(options: &NewGameOptions) -> String {
+    format!("Map size: < {} >", options.map_size.label())
+}
+
+fn move_focus(focus: &mut MenuFocus, buttons: usize, step: isize) {
+    if buttons > 0 {
+        focus.0 = (focus.0 as isize + step).rem_euclid(buttons as isize) as usize;
+    }
+}
+
+fn focused_action(focus: &MenuFocus, buttons: &Query<&MenuButton>) -> Option<MenuAction> {
+    buttons.iter().find(|button| button.order == focus.0).map(|button| button.action)
+}
+
+/// The focused entry stepped left or right, if it is adjustable.
+fn adjusted_action(focus: &MenuFocus, buttons: &Query<&MenuButton>, step: i32) -> Option<MenuAction> {
+    match focused_action(focus, buttons)? {
+        MenuAction::MapSize(_) => Some(MenuAction::MapSize(step)),
+        MenuAction::Setting(kind, _) if !matches!(kind, SettingKind::Bind(_)) => Some(MenuAction::Setting(kind, step)),
+        _ => None,
+    }
+}
+
+fn keyboard_navigation(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    capture: Res<MenuCapture>,
+    mut focus: ResMut<MenuFocus>,
+    buttons: Query<&MenuButton>,
+    mut actions: EventWriter<MenuAction>,
+) {
+    if capture.0 {
+        return;
+    }
+    let count = buttons.iter().len();
+    if keyboard.any_just_pressed([KeyCode::ArrowUp, KeyCode::KeyW]) {
+        move_focus(&mut focus, count, -1);
+    }
+    if keyboard.any_just_pressed([KeyCode::ArrowDown, KeyCode::KeyS, KeyCode::Tab]) {
+        move_focus(&mut focus, count, 1);
+    }
+    if keyboard.any_just_pressed([KeyCode::Enter, KeyCode::NumpadEnter, KeyCode::Space]) {
+        if let Some(action) = focused_action(&focus, &buttons) {
+            actions.send(action);
+        }
+    }
+    for (keys, step) in [([KeyCode::ArrowLeft, KeyCode::KeyA], -1), ([KeyCode::ArrowRight, KeyCode::KeyD], 1)] {
+        if keyboard.any_just_pressed(keys) {
+            if let Some(action) = adjusted_action(&focus, &buttons, step) {
+                actions.send(action);
+            }
+        }
+    }
+    if keyboard.just_pressed(KeyCode::Escape) {
+        actions.send(MenuAction::Back);
+    }
+}
+
+fn gamepad_navigation(
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    mut focus: ResMut<MenuFocus>,
+    buttons: Query<&MenuButton>,
+    mut actions: EventWriter<MenuAction>,
+) {
+    let count = buttons.iter().len();
+    for gamepad in gamepads.iter() {
+        let pressed = |button_type| gamepad_buttons.just_pressed(GamepadButton::new(gamepad, button_type));
+        if pressed(GamepadButtonType::DPadUp) {
+            move_focus(&mut focus, count, -1);
+        }
+        if pressed(GamepadButtonType::DPadDown) {
+            move_focus(&mut focus, count, 1);
+        }
+        if pressed(GamepadButtonType::South) {
+            if let Some(action) = focused_action(&focus, &buttons) {
+                actions.send(action);
+            }
+        }
+        for (button_type, step) in [(GamepadButtonType::DPadLeft, -1), (GamepadButtonType::DPadRight, 1)] {
+            if pressed(button_type) {
+                if let Some(action) = adjusted_action(&focus, &buttons, step) {
+                    actions.send(action);
+                }
+            }
+        }
+        if pressed(GamepadButtonType::East) {
+            actions.send(MenuAction::Back);
+        }
+    }
+}
+
+fn mouse_navigation(
+    query: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    mut focus: ResMut<MenuFocus>,
+    mut actions: EventWriter<MenuAction>,
+) {
+    for (interaction, button) in &query {
+        match interaction {
+            Interaction::Pressed => {
+                focus.0 = button.order;
+                actions.send(button.action);
+            }
+            Interaction::Hovered => focus.0 = button.order,
+            Interaction::None => {}
+        }
+    }
+}
+
+fn seed_input(mut key_events: EventReader<KeyboardInput>, mut options: ResMut<NewGameOptions>) {
+    for event in key_events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Backspace => {
+                options.seed.pop();
+            }
+            Key::Character(chars) => {
+                for digit in chars.chars().filter(char::is_ascii_digit) {
+                    if options.seed.len() < MAX_SEED_DIGITS {
+                        options.seed.push(digit);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn handle_menu_actions(
+    mut actions: EventReader<MenuAction>,
+    screen: Res<State<MenuScreen>>,
+    mut next_screen: ResMut<NextState<MenuScreen>>,
+    mut options: ResMut<NewGameOptions>,
+    (save_dir, settings): (Option<Res<SaveDir>>, Option<Res<Settings>>),
+    (mut new_game, mut load_game): (EventWriter<NewGameEvent>, EventWriter<LoadGameEvent>),
+    mut exit: EventWriter<AppExit>,
+) {
+    for action in actions.read() {
+        match action {
+            MenuAction::NewGame => next_screen.set(MenuScreen::NewGame),
+            MenuAction::Continue => {
+                if let Some(slot) = save_dir.as_ref().and_then(|dir| dir.latest()) {
+                    load_game.send(LoadGameEvent(slot));
+                }
+            }
+            MenuAction::Settings => next_screen.set(MenuScreen::Settings),
+            MenuAction::Credits => next_screen.set(MenuScreen::Credits),
+            MenuAction::Quit => {
+                exit.send(AppExit::Success);
+            }
+            MenuAction::Back => {
+                if *screen.get() != MenuScreen::Main {
+                    next_screen.set(MenuScreen::Main);
+                }
+            }
+            MenuAction::RandomSeed => options.seed = rand::random::<u32>().to_string(),
+            MenuAction::MapSize(step) => {
+                let index = MapSize::ALL.iter().position(|size| *size == options.map_size).unwrap_or_default() as i32;
+                options.map_size = MapSize::ALL[(index + step).rem_euclid(MapSize::ALL.len() as i32) as usize];
+            }
+            MenuAction::Start => {
+                let seed = options.seed.parse().unwrap_or_else(|_| rand::random());
+                let difficulty = settings.as_ref().map_or_else(Difficulty::default, |settings| settings.difficulty);
+                new_game.send(NewGameEvent { seed: WorldSeed(seed), map_size: options.map_size, difficulty });
+            }
+            // Handled by the screen that owns them
+            MenuAction::Setting(..) => {}
+        }
+    }
+}
+
+fn highlight_focused(focus: Res<MenuFocus>, mut query: Query<(&MenuButton, &mut BackgroundColor)>) {
+    for (button, mut color) in &mut query {
+        *color = if button.order == focus.0 { BUTTON_FOCUS_COLOR } else { BUTTON_COLOR }.into();
+    }
+}
+
+fn update_new_game_text(
+    options: Res<NewGameOptions>,
+    mut seed_query: Query<&mut Text, (With<SeedText>, Without<MapSizeText>)>,
+    mut map_size_query: Query<&mut Text, (With<MapSizeText>, Without<SeedText>)>,
+) {
+    if !options.is_changed() {
+        return;
+    }
+    for mut text in &mut seed_query {
+        text.sections[0].value = seed_label(&options);
+    }
+    for mut text in &mut map_size_query {
+        text.sections[0].value = map_size_label(&options);
+    }
+}
+
+fn start_new_game(
+    mut commands: Commands,
+    mut events: EventReader<NewGameEvent>,
+    (mut wave, mut next_state): (ResMut<WaveState>, ResMut<NextState<GameStatus>>),
+    mut player_query: Query<(&mut Player, &mut Transform, Option<&mut Interpolated>)>,
+    mut progress_query: Query<(&mut PlayerStats, &mut Experience, &mut Inventory, &mut Weapons)>,
+    (enemy_query, pickup_query): (Query<Entity, With<Enemy>>, Query<Entity, With<Pickup>>),
+    projectile_query: Query<Entity, With<Projectile>>,
+) {
+    let Some(event) = events.read().last() else { return; };
+
+    replace_terrain(&mut commands, generate_terrain(event.seed, event.map_size));
+    commands.insert_resource(event.map_size);
+    commands.insert_resource(event.difficulty);
+    commands.insert_resource(event.seed);
+    commands.insert_resource(GameRng::from_world_seed(event.seed));
+
+    for (mut player, mut transform, interpolated) in &mut player_query {
+        *player = Player::default();
+        transform.translation = PLAYER_SPAWN_POSITION;
+        if let Some(mut interpolated) = interpolated {
+            interpolated.teleport(PLAYER_SPAWN_POSITION);
+        }
+    }
+    for (mut stats, mut experience, mut inventory, mut weapons) in &mut progress_query {
+        *stats = PlayerStats::default();
+        *experience = Experience::default();
+        *inventory = Inventory::default();
+        *weapons = Weapons::default();
+    }
+    commands.insert_resource(UpgradeChoices::default());
+    commands.insert_resource(ProjectilePool::default());
+    for entity in enemy_query.iter().chain(&pickup_query).chain(&projectile_query) {
+        commands.entity(entity).despawn_recursive();
+    }
+    *wave = WaveState::default();
+    commands.insert_resource(GameClock::default());
+    commands.insert_resource(WeatherState::new(event.seed));
+    next_state.set(GameStatus::Started);
+}
@@ -0,0 +1,232 @@
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use crate::config::MINIMAP_SIZE;
+use crate::enemy::Enemy;
+use crate::player::Player;
+use crate::terrain::{in_vision, tile_world_size, Biome, TerrainMap};
+use crate::weather::WeatherState;
+use crate::GameStatus;
+
+const WORLD_MAP_KEY: KeyCode = KeyCode::KeyM;
+/// Enemy dots are pooled; enemies past this many are not drawn.
+const MAX_ENEMY_DOTS: usize = 64;
+const DOT_SIZE: f32 = 4.0;
+const WORLD_MAP_SIZE: f32 = 640.0;
+
+const UNEXPLORED_COLOR: [u8; 4] = [12, 10, 8, 255];
+const PLAYER_DOT_COLOR: Color = Color::srgb(1.0, 1.0, 1.0);
+const ENEMY_DOT_COLOR: Color = Color::srgb(0.9, 0.15, 0.1);
+const FRAME_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.7);
+
+/// Corner minimap and a full-screen world map (M), both drawn from one texture
+/// holding a pixel per terrain tile.
+pub struct MinimapPlugin;
+
+#[derive(Resource)]
+struct MinimapTexture(Handle<Image>);
+
+#[derive(Component)]
+struct Minimap;
+#[derive(Component)]
+struct WorldMap;
+#[derive(Component)]
+struct PlayerDot;
+#[derive(Component)]
+struct EnemyDot;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostStartup, minimap_spawn)
+            .add_systems(Update, (
+                render_map_texture.run_if(resource_exists_and_changed::<TerrainMap>),
+                toggle_world_map,
+                update_player_dots,
+                update_enemy_dots,
+            ));
+    }
+}
+
+fn biome_color(biome: Biome) -> [u8; 4] {
+    match biome {
+        Biome::Sand => [219, 199, 140, 255],
+        Biome::Grass => [89, 153, 64, 255],
+        Biome::DarkForest => [38, 89, 38, 255],
+        Biome::ShallowWater => [77, 140, 217, 255],
+        Biome::DeepWater => [31, 77, 166, 255],
+    }
+}
+
+fn minimap_spawn(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let image = images.add(Image::new_fill(
+        Extent3d { width: 1, height: 1, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        &UNEXPLORED_COLOR,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    ));
+
+    // Bottom right corner
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                right: Val::Px(12.0),
+                bottom: Val::Px(12.0),
+                padding: UiRect::all(Val::Px(3.0)),
+                ..default()
+            },
+            background_color: FRAME_COLOR.into(),
+            ..default()
+        },
+        Minimap,
+    )).with_children(|parent| {
+        spawn_map_image(parent, image.clone(), MINIMAP_SIZE, true);
+    });
+
+    // Full screen map, hidden until toggled
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            background_color: FRAME_COLOR.into(),
+            visibility: Visibility::Hidden,
+            z_index: ZIndex::Global(40),
+            ..default()
+        },
+        WorldMap,
+    )).with_children(|parent| {
+        spawn_map_image(parent, image.clone(), WORLD_MAP_SIZE, false);
+    });
+
+    commands.insert_resource(MinimapTexture(image));
+}
+
+fn spawn_map_image(parent: &mut ChildBuilder, image: Handle<Image>, size: f32, enemy_dots: bool) {
+    parent.spawn(ImageBundle {
+        style: Style {
+            width: Val::Px(size),
+            height: Val::Px(size),
+            ..default()
+        },
+        image: UiImage::new(image),
+        ..default()
+    }).with_children(|map| {
+        if enemy_dots {
+            for _ in 0..MAX_ENEMY_DOTS {
+                map.spawn((dot(ENEMY_DOT_COLOR), EnemyDot));
+            }
+        }
+        map.spawn((dot(PLAYER_DOT_COLOR), PlayerDot));
+    });
+}
+
+fn dot(color: Color) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            width: Val::Px(DOT_SIZE),
+            height: Val::Px(DOT_SIZE),
+            margin: UiRect::all(Val::Px(-DOT_SIZE / 2.0)),
+            ..default()
+        },
+        background_color: color.into(),
+        visibility: Visibility::Hidden,
+        ..default()
+    }
+}
+
+/// One pixel per tile, image rows top to bottom so +y points up like the world.
+fn render_map_texture(terrain: Res<TerrainMap>, texture: Res<MinimapTexture>, mut images: ResMut<Assets<Image>>) {
+    let Some(image) = images.get_mut(&texture.0) else { return; };
+    let (width, height) = (terrain.rows as u32, terrain.columns as u32);
+    if image.width() != width || image.height() != height {
+        image.resize(Extent3d { width, height, depth_or_array_layers: 1 });
+    }
+
+    for ((x, y), tile) in terrain.tiles() {
+        let color = if terrain.is_explored(x, y) { biome_color(tile.biome) } else { UNEXPLORED_COLOR };
+        let pixel = ((height - 1 - y as u32) * width + x as u32) as usize * 4;
+        image.data[pixel..pixel + 4].copy_from_slice(&color);
+    }
+}
+
+/// World position as a fraction of the map, (0, 0) being the top left corner.
+fn map_fraction(terrain: &TerrainMap, position: Vec2) -> Vec2 {
+    let grid = position / tile_world_size() + Vec2::splat(0.5);
+    Vec2::new(grid.x / terrain.rows as f32, 1.0 - grid.y / terrain.columns as f32)
+}
+
+fn place_dot(style: &mut Style, visibility: &mut Visibility, fraction: Vec2) {
+    let inside = (0.0..=1.0).contains(&fraction.x) && (0.0..=1.0).contains(&fraction.y);
+    *visibility = if inside { Visibility::Inherited } else { Visibility::Hidden };
+    style.left = Val::Percent(fraction.x * 100.0);
+    style.top = Val::Percent(fraction.y * 100.0);
+}
+
+fn update_player_dots(
+    terrain: Option<Res<TerrainMap>>,
+    player_query: Query<&Transform, With<Player>>,
+    mut dot_query: Query<(&mut Style, &mut Visibility), With<PlayerDot>>,
+) {
+    let (Some(terrain), Ok(player)) = (terrain, player_query.get_single()) else { return; };
+    let fraction = map_fraction(&terrain, player.translation.truncate());
+    for (mut style, mut visibility) in &mut dot_query {
+        place_dot(&mut style, &mut visibility, fraction);
+    }
+}
+
+fn update_enemy_dots(
+    terrain: Option<Res<TerrainMap>>,
+    weather: Res<WeatherState>,
+    player_query: Query<&Transform, With<Player>>,
+    enemy_query: Query<&Transform, (With<Enemy>, Without<Player>)>,
+    mut dot_query: Query<(&mut Style, &mut Visibility), With<EnemyDot>>,
+) {
+    let (Some(terrain), Ok(player)) = (terrain, player_query.get_single()) else { return; };
+    let player_position = player.translation.truncate();
+    // Same as the fog: only enemies the player can currently see
+    let mut nearby = enemy_query.iter()
+        .map(|transform| transform.translation.truncate())
+        .filter(|position| in_vision(player_position, *position, weather.vision_radius()));
+
+    for (mut style, mut visibility) in &mut dot_query {
+        match nearby.next() {
+            Some(position) => place_dot(&mut style, &mut visibility, map_fraction(&terrain, position)),
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+fn toggle_world_map(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    state: Res<State<GameStatus>>,
+    mut world_map: Query<&mut Visibility, With<WorldMap>>,
+    mut minimap: Query<&mut Visibility, (With<Minimap>, Without<WorldMap>)>,
+) {
+    let Ok(mut world_map) = world_map.get_single_mut() else { return; };
+    let in_game = *state.get() == GameStatus::Started;
+    let open = if !in_game {
+        false
+    } else if keyboard.just_pressed(WORLD_MAP_KEY) {
+        *world_map == Visibility::Hidden
+    } else {
+        *world_map != Visibility::Hidden
+    };
+
+    let (map_visibility, minimap_visibility) = if open {
+        (Visibility::Inherited, Visibility::Hidden)
+    } else {
+        (Visibility::Hidden, Visibility::Inherited)
+    };
+    world_map.set_if_neq(map_visibility);
+    for mut visibility in &mut minimap {
+        visibility.set_if_neq(minimap_visibility);
+    }
+}
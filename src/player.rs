@@ -1,21 +1,75 @@
 use bevy::{input::*, prelude::*};
-use bevy::math::vec3;
-use bevy::prelude::KeyCode::{KeyA, KeyD, KeyS, KeyW, ShiftLeft};
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 use crate::assets_loader::TextureGameAssets;
 use crate::config::*;
+use crate::inventory::{Inventory, HOTBAR_KEYS};
+use crate::leveling::Experience;
+use crate::logging::PLAYER_TARGET;
+use crate::terrain::TerrainMap;
+use crate::timestep::{Interpolated, SimSet};
+use crate::weapons::Weapons;
+use crate::weather::WeatherState;
+use crate::GameStatus;
 
-pub struct PlayerPlugin;
+#[derive(Default)]
+pub struct PlayerPlugin {
+    pub headless: bool,
+}
 
-#[derive(Component)]
-struct PlayerCollider;
+/// Movement intent for the current frame. Filled from the keyboard when running
+/// with a window, or written directly by scripted input in headless runs.
+#[derive(Resource, Default, Clone, Copy, Debug, PartialEq)]
+pub struct PlayerInput {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub sprint: bool,
+    /// Chop, break or pick whatever is next to the player.
+    pub interact: bool,
+    /// Inventory slot to use an item from, consumed by the tick that handles it.
+    pub use_slot: Option<u8>,
+    /// Inventory slots to move items from and to, consumed like `use_slot`.
+    pub move_slot: Option<(u8, u8)>,
+    /// Upgrade taken from the level-up choices, by position.
+    pub pick_upgrade: Option<u8>,
+}
+
+/// Where a new game starts the player.
+pub const PLAYER_SPAWN_POSITION: Vec3 = Vec3::new(10000.0, 10000.0, 1.0);
+
+/// Keys read into `PlayerInput`. Rebindable from the settings screen.
+/// Keys missing from an older settings file keep their default.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub up: KeyCode,
+    pub down: KeyCode,
+    pub left: KeyCode,
+    pub right: KeyCode,
+    pub sprint: KeyCode,
+    pub interact: KeyCode,
+}
 
 #[derive(Component)]
 pub struct Player{
     pub health: i32,
-    stamina: f32,
+    pub stamina: f32,
     check_run: bool,
 }
+
+/// What upgrades changed about the player. Speeds and regeneration multiply the
+/// base values from the config; attack stats scale whatever weapons deal.
+#[derive(Component, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PlayerStats {
+    pub max_health: i32,
+    pub move_speed: f32,
+    pub sprint_speed: f32,
+    pub stamina_regen: f32,
+    pub attack_damage: f32,
+    pub attack_area: f32,
+}
 #[derive(Resource, Default)]
 struct CurrentPlayerState(PlayerState);
 #[derive(Resource)]
@@ -51,18 +105,66 @@ impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(PlayerSpriteIndex(0))
             .insert_resource(CurrentPlayerState::default())
+            .insert_resource(PlayerInput::default())
+            .init_resource::<KeyBindings>()
             .add_systems(PostStartup,
                 player_spawn)
-            .add_systems(Update,(
-                animate_sprite,
-                camera_follow_player.after(move_player),
-                move_player,
-                player_position_mapping,
-                player_stamina_health,
-            ))
+            .add_systems(FixedUpdate,
+                move_player.in_set(SimSet::Player),
+            )
         ;
+        if !self.headless {
+            app.add_systems(Update,(
+                read_player_input,
+                attach_player_sprite,
+                animate_sprite,
+            ));
+        }
+    }
+}
+
+impl PlayerPlugin {
+    /// Player logic only: no sprite or keyboard handling.
+    pub fn headless() -> Self {
+        Self { headless: true }
+    }
+}
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            up: KeyCode::KeyW,
+            down: KeyCode::KeyS,
+            left: KeyCode::KeyA,
+            right: KeyCode::KeyD,
+            sprint: KeyCode::ShiftLeft,
+            interact: KeyCode::KeyE,
+        }
+    }
+}
+
+impl Default for PlayerStats {
+    fn default() -> Self {
+        Self {
+            max_health: PLAYER_MAX_HEALTH,
+            move_speed: 1.0,
+            sprint_speed: 1.0,
+            stamina_regen: 1.0,
+            attack_damage: 1.0,
+            attack_area: 1.0,
+        }
     }
 }
+
+impl Default for Player {
+    fn default() -> Self {
+        Self {
+            health: PLAYER_MAX_HEALTH,
+            stamina: PLAYER_MAX_STAMINA,
+            check_run: true,
+        }
+    }
+}
+
 #[derive(Component)]
 struct AnimationIndices {
     first: usize,
@@ -71,148 +173,162 @@ struct AnimationIndices {
 
 fn player_spawn(
     mut commands: Commands,
-    handler : Res<TextureGameAssets>,
-    mut texture_atlas: ResMut<Assets<TextureAtlasLayout>>
 ){
-    println!("Player Spawned");
-
-    let texture = handler.player_handler.clone();
-    let layout = TextureAtlasLayout::from_grid(UVec2::splat(SPRITE_SCALE_FACTOR), 8, 16, None, None);
-    let texture_atlas = texture_atlas.add(layout);
-    let animation_indices = AnimationIndices { first: 0, last: 7 };
     commands.spawn((
-        SpriteBundle {//rand::thread_rng().gen_range(0.0..100.0),rand::thread_rng().gen_range(0.0..100.0) -> random x y pos
-            transform: Transform::from_translation(vec3(10000.0,10000.0,1.0)).with_scale(Vec3::splat(2.5)),
-            texture : texture.unwrap(),
-            ..default()
-        },
-        TextureAtlas {
-            layout: texture_atlas,
-            index: animation_indices.first,
-        },
-        Player{
-            health:100,
-            stamina:100.0,
-            check_run: true,
-        },
-        AnimationTimer(Timer::from_seconds(ANIMATION_INTERVAL, TimerMode::Repeating)),
-    ));
-
-    commands.spawn(TextBundle::from_sections([
-        TextSection::new("{ x : ",TextStyle{font_size:14.5,..default()}),
-        TextSection::new("",TextStyle{font_size:14.5,..default()}),
-        TextSection::new(",",TextStyle{font_size:14.5,..default()}),
-        TextSection::new("y : ",TextStyle{font_size:14.5,..default()}),
-        TextSection::new("",TextStyle{font_size:14.5,..default()}),
-        TextSection::new("| {Health: ",TextStyle{font_size:14.5,..default()}),
-        TextSection::new("",TextStyle{font_size:14.5,..default()}),
-        TextSection::new("| {Stamina: ",TextStyle{font_size:14.5,..default()}),
-        TextSection::new("",TextStyle{font_size:14.5,..default()}),
-
-    ]).with_style(
-        Style{
-            position_type: PositionType::Absolute,
-            ..default()
-        }
+        SpatialBundle::from_transform(Transform::from_translation(PLAYER_SPAWN_POSITION).with_scale(Vec3::splat(2.5))),
+        Interpolated::new(PLAYER_SPAWN_POSITION),
+        Player::default(),
+        PlayerStats::default(),
+        Experience::default(),
+        Inventory::default(),
+        Weapons::default(),
     ));
-
+    info!(target: PLAYER_TARGET, "Player spawned");
 }
-fn player_stamina_health
-(
-    mut query : Query<&mut Text>,
-    player_query : Query<&Player>,
+
+fn attach_player_sprite(
+    mut commands: Commands,
+    query: Query<Entity, Added<Player>>,
+    handler : Res<TextureGameAssets>,
+    mut texture_atlas: ResMut<Assets<TextureAtlasLayout>>
 ){
-    let mut position_text = query.single_mut();
-    position_text.sections[6].value = player_query.single().health.to_string()+" } ";
-    position_text.sections[8].value = (player_query.single().stamina as i32).to_string()+" } ";
+    for entity in &query {
+        let texture = handler.player_handler.clone();
+        let layout = TextureAtlasLayout::from_grid(UVec2::splat(SPRITE_SCALE_FACTOR), 8, 16, None, None);
+        let texture_atlas = texture_atlas.add(layout);
+        let animation_indices = AnimationIndices { first: 0, last: 7 };
+        commands.entity(entity).insert((
+            Sprite::default(),
+            texture.unwrap(),
+            TextureAtlas {
+                layout: texture_atlas,
+                index: animation_indices.first,
+            },
+            AnimationTimer(Timer::from_seconds(ANIMATION_INTERVAL, TimerMode::Repeating)),
+        ));
+    }
 }
-fn player_position_mapping(
-    mut query : Query<&mut Text>,
-    player_query : Query<&Transform,With<Player>>
+
+fn read_player_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    state: Res<State<GameStatus>>,
+    mut input: ResMut<PlayerInput>,
 ){
-    let transform = player_query.single();
-    let mut position_text = query.single_mut();
-    position_text.sections[1].value = (transform.translation.x as i32).to_string();
-    position_text.sections[4].value = (transform.translation.y as i32).to_string()+" } ";
+    // A use, move or pick that no tick handled yet stays pending. The number keys
+    // pick upgrades while leveling up, so they only reach the hotbar in game.
+    let hotbar = HOTBAR_KEYS.iter()
+        .position(|key| keyboard.just_pressed(*key))
+        .filter(|_| *state.get() == GameStatus::Started);
+    *input = PlayerInput {
+        up: keyboard.pressed(bindings.up),
+        down: keyboard.pressed(bindings.down),
+        left: keyboard.pressed(bindings.left),
+        right: keyboard.pressed(bindings.right),
+        sprint: keyboard.pressed(bindings.sprint),
+        interact: keyboard.pressed(bindings.interact),
+        use_slot: hotbar.map(|slot| slot as u8).or(input.use_slot),
+        move_slot: input.move_slot,
+        pick_upgrade: input.pick_upgrade,
+    };
 }
-fn camera_follow_player(
-    query_player : Query<&Transform,With<Player>>,
-    mut query_camera : Query<&mut Transform,(Without<Player>,With<Camera>)>
-){
-    let player_transform = query_player.single();
-    let mut camera_transform = query_camera.single_mut();
 
-    camera_transform.translation.x = player_transform.translation.x;
-    camera_transform.translation.y = player_transform.translation.y;
-}
 fn move_player(
-    mut player_query: Query<(&mut Player,&mut Transform),With<Player>>  ,
+    mut player_query: Query<(&mut Player,&PlayerStats,&mut Transform)>,
     mut player_state: ResMut<CurrentPlayerState>,
-    keyboard: Res<ButtonInput<KeyCode>>,
+    input: Res<PlayerInput>,
     time: Res<Time>,
+    weather: Res<WeatherState>,
+    terrain: Option<Res<TerrainMap>>,
 ){
-    for (mut player_ent,mut transform) in &mut player_query.iter_mut(){
+    for (mut player_ent,stats,mut transform) in &mut player_query.iter_mut(){
         if !player_state.is_dead() {
 
+            let before = transform.translation;
+            let biome = terrain.as_ref().and_then(|terrain| terrain.biome_at(before.truncate()));
+            let movement = biome.map(|biome| biome.movement()).unwrap_or_default();
+            let sprint_speed = SPECIAL_SPEED * stats.sprint_speed;
+            let walk_speed = NORMAL_SPEED * stats.move_speed;
+            let mut step = movement.speed * weather.speed_multiplier(biome) * time.delta_seconds();
+            // Out of breath in deep water, barely staying afloat
+            if movement.swimming && player_ent.stamina <= 0.0 {
+                step *= 0.5;
+            }
+
             let mut is_moving = false;
 
-            if player_ent.check_run && keyboard.pressed(ShiftLeft) {
-                if keyboard.pressed(KeyW){
-                    transform.translation.y += SPECIAL_SPEED * time.delta_seconds();
+            if player_ent.check_run && input.sprint && !movement.swimming {
+                if input.up{
+                    transform.translation.y += sprint_speed * step;
                     player_state.set_state(PlayerState::RunFront);
                     is_moving = true;
                 }
-                if keyboard.pressed(KeyS) {
-                    transform.translation.y -= SPECIAL_SPEED * time.delta_seconds();
+                if input.down {
+                    transform.translation.y -= sprint_speed * step;
                     player_state.set_state(PlayerState::RunBack);
                     is_moving = true;
                 }
-                if keyboard.pressed(KeyD) {
-                    transform.translation.x += SPECIAL_SPEED * time.delta_seconds();
+                if input.right {
+                    transform.translation.x += sprint_speed * step;
                     player_state.set_state(PlayerState::RunRight);
                     is_moving = true;
                 }
-                if keyboard.pressed(KeyA) {
-                    transform.translation.x -= SPECIAL_SPEED * time.delta_seconds();
+                if input.left {
+                    transform.translation.x -= sprint_speed * step;
                     player_state.set_state(PlayerState::RunLeft);
                     is_moving = true;
                 }
-                player_ent.stamina -= 10.0*time.delta_seconds();
-                println!("stamina reduced: {}",player_ent.stamina);
+                player_ent.stamina -= 10.0*movement.stamina_drain*time.delta_seconds();
+                trace!(target: PLAYER_TARGET, "stamina reduced: {}", player_ent.stamina);
                 if player_ent.stamina <= 0.0 {
                     player_ent.check_run = false;
+                    debug!(target: PLAYER_TARGET, "stamina exhausted, sprint disabled");
                 }
             } else  {
-                if keyboard.pressed(KeyW) {
-                    transform.translation.y += NORMAL_SPEED * time.delta_seconds();
+                if input.up {
+                    transform.translation.y += walk_speed * step;
                     player_state.set_state(PlayerState::WalkFront);
                     is_moving = true;
                 }
-                if keyboard.pressed(KeyS) {
-                    transform.translation.y -= NORMAL_SPEED * time.delta_seconds();
+                if input.down {
+                    transform.translation.y -= walk_speed * step;
                     player_state.set_state(PlayerState::WalkBack);
                     is_moving = true;
                 }
-                if keyboard.pressed(KeyD) {
-                    transform.translation.x += NORMAL_SPEED * time.delta_seconds();
+                if input.right {
+                    transform.translation.x += walk_speed * step;
                     player_state.set_state(PlayerState::WalkRight);
                     is_moving = true;
                 }
-                if keyboard.pressed(KeyA) {
-                    transform.translation.x -= NORMAL_SPEED * time.delta_seconds();
+                if input.left {
+                    transform.translation.x -= walk_speed * step;
                     player_state.set_state(PlayerState::WalkLeft);
                     is_moving = true;
                 }
 
-                if player_ent.stamina < 100.0 {
-                    player_ent.stamina += 5.0*time.delta_seconds();
-                    println!("stamina: {}",player_ent.stamina)
+                if movement.swimming {
+                    player_ent.stamina = (player_ent.stamina - SWIM_STAMINA_DRAIN*time.delta_seconds()).max(0.0);
+                    trace!(target: PLAYER_TARGET, "swimming, stamina: {}", player_ent.stamina);
+                } else if player_ent.stamina < PLAYER_MAX_STAMINA {
+                    player_ent.stamina += 5.0*stats.stamina_regen*time.delta_seconds();
+                    trace!(target: PLAYER_TARGET, "stamina: {}", player_ent.stamina);
                 }
                 if player_ent.stamina >= 25.0{
                     player_ent.check_run = true;
                 }
             }
-            //println!("Player Status: {:?}",player_state.0);
+            // Trees and rocks are solid: undo the part of the move that walks into one.
+            // Standing on one already (spawned there) never traps the player.
+            if let Some(terrain) = &terrain {
+                if !terrain.is_blocked(before.truncate()) {
+                    if terrain.is_blocked(Vec2::new(transform.translation.x, before.y)) {
+                        transform.translation.x = before.x;
+                    }
+                    if terrain.is_blocked(transform.translation.truncate()) {
+                        transform.translation.y = before.y;
+                    }
+                }
+            }
             if !is_moving {
                 match player_state.0 {
                     PlayerState::WalkBack | PlayerState::RunBack => {
@@ -285,19 +401,6 @@ fn animate_sprite(
 }
 
 impl CurrentPlayerState {
-    fn is_alive(&self) -> bool {
-        self.0 == PlayerState::Alive
-    }
-    fn is_walking(&self) -> bool {
-        match self.0 {
-            PlayerState::Walk => true,
-            PlayerState::WalkBack => true,
-            PlayerState::WalkFront => true,
-            PlayerState::WalkLeft => true,
-            PlayerState::WalkRight => true,
-            _ => false
-        }
-    }
     fn is_dead(&self) -> bool{
         match self.0{
             PlayerState::DeadFront => true,
@@ -307,16 +410,6 @@ impl CurrentPlayerState {
             _ => false
         }
     }
-    fn is_running(&self) -> bool {
-        match self.0 {
-            PlayerState::Run => true,
-            PlayerState::RunBack => true,
-            PlayerState::RunFront => true,
-            PlayerState::RunLeft => true,
-            PlayerState::RunRight => true,
-            _ => false
-        }
-    }
     fn set_state(&mut self,new_state: PlayerState){
         self.0 = new_state;
     }
@@ -0,0 +1,206 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use crate::combat::{DamageDealt, Team};
+use crate::config::{PLAYER_RADIUS, PROJECTILE_LIFETIME};
+use crate::enemy::Enemy;
+use crate::player::Player;
+use crate::spatial::EnemyGrid;
+use crate::timestep::SimSet;
+
+/// Above vegetation and pickups, under the fog.
+pub const PROJECTILE_Z: f32 = 0.4;
+const PROJECTILE_SIZE: Vec2 = Vec2::new(22.0, 8.0);
+const PLAYER_PROJECTILE_COLOR: Color = Color::srgb(0.75, 0.75, 0.8);
+const ENEMY_PROJECTILE_COLOR: Color = Color::srgb(0.9, 0.35, 0.2);
+
+/// Projectiles fired by either side. Shots are requested with `FireProjectile`
+/// and collide with the other team: enemies through the `EnemyGrid`, the player
+/// directly. Hits send `DamageDealt` like any other attack.
+///
+/// Spent projectiles are hidden and parked in a pool per team instead of being
+/// despawned, so sustained fire reuses the same entities.
+#[derive(Default)]
+pub struct ProjectilePlugin {
+    pub headless: bool,
+}
+
+#[derive(Component, Clone, Debug)]
+pub struct Projectile {
+    pub team: Team,
+    pub damage: i32,
+    pub velocity: Vec2,
+    pub radius: f32,
+    /// Targets it passes through after the first hit.
+    pub pierce: u32,
+    lifetime: f32,
+    /// Targets hit so far, so a piercing projectile hits each one once.
+    hits: Vec<Entity>,
+    active: bool,
+}
+
+/// Asks for a projectile, taken from the pool when one is free.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct FireProjectile {
+    pub team: Team,
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub damage: i32,
+    pub radius: f32,
+    pub pierce: u32,
+}
+
+/// Parked projectile entities, by team so a reused one keeps its sprite.
+#[derive(Resource, Default)]
+pub struct ProjectilePool {
+    free: HashMap<Team, Vec<Entity>>,
+}
+
+impl Plugin for ProjectilePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ProjectilePool>()
+            .add_event::<FireProjectile>()
+            .add_systems(FixedUpdate, (spawn_projectiles, move_projectiles).chain().in_set(SimSet::Action));
+        if !self.headless {
+            app.add_systems(Update, attach_projectile_sprite);
+        }
+    }
+}
+
+impl ProjectilePlugin {
+    /// Projectile logic only: no sprites.
+    pub fn headless() -> Self {
+        Self { headless: true }
+    }
+}
+
+impl Projectile {
+    /// Whether it is in flight rather than parked in the pool.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    fn launch(&mut self, shot: &FireProjectile) {
+        self.team = shot.team;
+        self.damage = shot.damage;
+        self.velocity = shot.velocity;
+        self.radius = shot.radius;
+        self.pierce = shot.pierce;
+        self.lifetime = PROJECTILE_LIFETIME;
+        self.hits.clear();
+        self.active = true;
+    }
+}
+
+impl From<&FireProjectile> for Projectile {
+    fn from(shot: &FireProjectile) -> Self {
+        Self {
+            team: shot.team,
+            damage: shot.damage,
+            velocity: shot.velocity,
+            radius: shot.radius,
+            pierce: shot.pierce,
+            lifetime: PROJECTILE_LIFETIME,
+            hits: Vec::new(),
+            active: true,
+        }
+    }
+}
+
+impl ProjectilePool {
+    /// Parked entities of every team.
+    pub fn len(&self) -> usize {
+        self.free.values().map(Vec::len).sum()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+fn projectile_transform(position: Vec2, velocity: Vec2) -> Transform {
+    Transform::from_translation(position.extend(PROJECTILE_Z))
+        .with_rotation(Quat::from_rotation_z(velocity.y.atan2(velocity.x)))
+}
+
+pub(crate) fn spawn_projectiles(
+    mut commands: Commands,
+    mut shots: EventReader<FireProjectile>,
+    mut pool: ResMut<ProjectilePool>,
+    mut query: Query<(&mut Projectile, &mut Transform, &mut Visibility)>,
+) {
+    for shot in shots.read() {
+        let transform = projectile_transform(shot.position, shot.velocity);
+        let parked = pool.free.get_mut(&shot.team).and_then(Vec::pop);
+        match parked.and_then(|entity| query.get_mut(entity).ok()) {
+            Some((mut projectile, mut projectile_transform, mut visibility)) => {
+                projectile.launch(shot);
+                *projectile_transform = transform;
+                *visibility = Visibility::Inherited;
+            }
+            None => {
+                commands.spawn((SpatialBundle::from_transform(transform), Projectile::from(shot)));
+            }
+        }
+    }
+}
+
+fn move_projectiles(
+    time: Res<Time>,
+    grid: Res<EnemyGrid>,
+    mut pool: ResMut<ProjectilePool>,
+    mut damage: EventWriter<DamageDealt>,
+    mut projectile_query: Query<(Entity, &mut Projectile, &mut Transform, &mut Visibility)>,
+    enemy_query: Query<&Enemy>,
+    player_query: Query<(Entity, &Player, &Transform), Without<Projectile>>,
+) {
+    let delta = time.delta_seconds();
+    let player = player_query.get_single().ok().map(|(entity, _, transform)| (entity, transform.translation.truncate()));
+    for (entity, mut projectile, mut transform, mut visibility) in &mut projectile_query {
+        if !projectile.active {
+            continue;
+        }
+        transform.translation += (projectile.velocity * delta).extend(0.0);
+        projectile.lifetime -= delta;
+
+        let position = transform.translation.truncate();
+        let targets: Vec<Entity> = match projectile.team {
+            Team::Player => grid.within(position, projectile.radius)
+                .filter(|target| enemy_query.get(*target).is_ok_and(|enemy| enemy.health > 0))
+                .collect(),
+            Team::Enemy => player
+                .filter(|(_, at)| at.distance(position) <= projectile.radius + PLAYER_RADIUS)
+                .map(|(target, _)| target)
+                .into_iter()
+                .collect(),
+        };
+        for target in targets {
+            if projectile.hits.contains(&target) || projectile.hits.len() > projectile.pierce as usize {
+                continue;
+            }
+            projectile.hits.push(target);
+            damage.send(DamageDealt { target, amount: projectile.damage, team: projectile.team });
+        }
+
+        if projectile.lifetime <= 0.0 || projectile.hits.len() > projectile.pierce as usize {
+            projectile.active = false;
+            *visibility = Visibility::Hidden;
+            pool.free.entry(projectile.team).or_default().push(entity);
+        }
+    }
+}
+
+fn attach_projectile_sprite(mut commands: Commands, query: Query<(Entity, &Projectile), Added<Projectile>>) {
+    for (entity, projectile) in &query {
+        let color = match projectile.team {
+            Team::Player => PLAYER_PROJECTILE_COLOR,
+            Team::Enemy => ENEMY_PROJECTILE_COLOR,
+        };
+        commands.entity(entity).insert((
+            Sprite {
+                color,
+                custom_size: Some(PROJECTILE_SIZE),
+                ..default()
+            },
+            Handle::<Image>::default(),
+        ));
+    }
+}
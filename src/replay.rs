@@ -0,0 +1,292 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use bevy::prelude::*;
+use crate::enemy::{Difficulty, Enemy};
+use crate::logging::REPLAY_TARGET;
+use crate::player::{Player, PlayerInput};
+use crate::rng::WorldSeed;
+use crate::timestep::{SimSet, TickRate};
+use crate::GameStatus;
+
+const MAGIC: &[u8; 4] = b"ORCR";
+const VERSION: u16 = 4;
+
+/// Seed, tick rate, difficulty and one input per fixed tick, plus a hash of the
+/// state the recording ended in. Inputs are stored run-length encoded, four bytes
+/// each: the held keys and upgrade pick, the slot used that tick, then the slots
+/// an item was moved from and to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replay {
+    pub seed: WorldSeed,
+    pub tick_rate: f64,
+    pub difficulty: Difficulty,
+    pub inputs: Vec<PlayerInput>,
+    pub final_hash: u64,
+}
+
+pub enum ReplayMode {
+    Record(PathBuf),
+    Playback(Replay),
+}
+
+/// Needs `RngPlugin` and `TimestepPlugin` added first, since a recording
+/// starts from the current `WorldSeed` and fixed tick rate.
+pub struct ReplayPlugin {
+    pub mode: ReplayMode,
+}
+
+/// Inserted once a playback consumed all its inputs.
+#[derive(Resource, Debug, PartialEq, Eq)]
+pub enum ReplayResult {
+    Matched,
+    Mismatched { expected: u64, actual: u64 },
+}
+
+#[derive(Resource)]
+struct ReplayRecorder {
+    path: PathBuf,
+    replay: Replay,
+}
+
+#[derive(Resource)]
+struct ReplayPlayback {
+    replay: Replay,
+    tick: usize,
+}
+
+/// Hash of the simulated state, refreshed after every tick.
+#[derive(Resource, Default)]
+struct StateHash(u64);
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(StateHash::default())
+            .add_systems(FixedPostUpdate, update_state_hash);
+
+        match &self.mode {
+            ReplayMode::Record(path) => {
+                let seed = *app.world().resource::<WorldSeed>();
+                let tick_rate = app.world().resource::<TickRate>().0;
+                let difficulty = app.world().get_resource::<Difficulty>().copied().unwrap_or_default();
+                app.insert_resource(ReplayRecorder {
+                    path: path.clone(),
+                    replay: Replay { seed, tick_rate, difficulty, inputs: Vec::new(), final_hash: 0 },
+                })
+                .add_systems(First, restart_recording.run_if(resource_changed::<WorldSeed>))
+                .add_systems(FixedUpdate, record_input.in_set(SimSet::Input))
+                .add_systems(Last, save_recording_on_exit);
+            }
+            ReplayMode::Playback(replay) => {
+                app.insert_resource(replay.difficulty)
+                    .insert_resource(ReplayPlayback { replay: replay.clone(), tick: 0 })
+                    .add_systems(FixedUpdate, playback_input.in_set(SimSet::Input))
+                    .add_systems(Update, resume_after_level_up.run_if(in_state(GameStatus::LevelUp)))
+                    .add_systems(FixedLast, check_playback_finished);
+            }
+        }
+    }
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::decode(&mut BufReader::new(File::open(path)?))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.encode(&mut writer)?;
+        writer.flush()
+    }
+
+    fn encode(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&self.seed.0.to_le_bytes())?;
+        writer.write_all(&self.tick_rate.to_le_bytes())?;
+        writer.write_all(&[self.difficulty as u8])?;
+        writer.write_all(&(self.inputs.len() as u32).to_le_bytes())?;
+
+        let mut runs: Vec<(u16, u32)> = Vec::new();
+        for input in &self.inputs {
+            let bits = pack_input(input);
+            match runs.last_mut() {
+                Some((count, last)) if *last == bits && *count < u16::MAX => *count += 1,
+                _ => runs.push((1, bits)),
+            }
+        }
+        writer.write_all(&(runs.len() as u32).to_le_bytes())?;
+        for (count, bits) in runs {
+            writer.write_all(&count.to_le_bytes())?;
+            writer.write_all(&bits.to_le_bytes())?;
+        }
+        writer.write_all(&self.final_hash.to_le_bytes())
+    }
+
+    fn decode(reader: &mut impl Read) -> io::Result<Self> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not an Orc Wars replay"));
+        }
+        let version = u16::from_le_bytes(read_array(reader)?);
+        if version == 0 || version > VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("unsupported replay version {}", version)));
+        }
+        let seed = WorldSeed(u64::from_le_bytes(read_array(reader)?));
+        let tick_rate = f64::from_le_bytes(read_array(reader)?);
+        // Version 1 predates difficulty settings, so it always ran on Normal
+        let difficulty = if version >= 2 {
+            let [index] = read_array(reader)?;
+            *Difficulty::ALL.get(index as usize)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("unknown difficulty {}", index)))?
+        } else {
+            Difficulty::Normal
+        };
+        let ticks = u32::from_le_bytes(read_array(reader)?) as usize;
+
+        let runs = u32::from_le_bytes(read_array(reader)?);
+        let mut inputs = Vec::with_capacity(ticks);
+        for _ in 0..runs {
+            let count = u16::from_le_bytes(read_array(reader)?);
+            // Before version 4 inputs had no item moves, and before version 3 no
+            // item use either, so they were stored in fewer bytes
+            let bits = if version >= 4 {
+                u32::from_le_bytes(read_array(reader)?)
+            } else if version == 3 {
+                u16::from_le_bytes(read_array(reader)?) as u32
+            } else {
+                let [keys] = read_array(reader)?;
+                keys as u32
+            };
+            inputs.extend(std::iter::repeat(unpack_input(bits)).take(count as usize));
+        }
+        if inputs.len() != ticks {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "replay input stream is truncated"));
+        }
+        let final_hash = u64::from_le_bytes(read_array(reader)?);
+
+        Ok(Self { seed, tick_rate, difficulty, inputs, final_hash })
+    }
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn pack_input(input: &PlayerInput) -> u32 {
+    (input.up as u32)
+        | (input.down as u32) << 1
+        | (input.left as u32) << 2
+        | (input.right as u32) << 3
+        | (input.sprint as u32) << 4
+        | (input.interact as u32) << 5
+        | input.pick_upgrade.map_or(0, |pick| pick as u32 + 1) << 6
+        | input.use_slot.map_or(0, |slot| slot as u32 + 1) << 8
+        | input.move_slot.map_or(0, |(from, to)| (from as u32 + 1) | (to as u32) << 8) << 16
+}
+
+fn unpack_input(bits: u32) -> PlayerInput {
+    PlayerInput {
+        up: bits & 1 != 0,
+        down: bits & 1 << 1 != 0,
+        left: bits & 1 << 2 != 0,
+        right: bits & 1 << 3 != 0,
+        sprint: bits & 1 << 4 != 0,
+        interact: bits & 1 << 5 != 0,
+        pick_upgrade: (bits >> 6 & 0b11).checked_sub(1).map(|pick| pick as u8),
+        use_slot: (bits >> 8 & 0xff).checked_sub(1).map(|slot| slot as u8),
+        move_slot: (bits >> 16 & 0xff).checked_sub(1).map(|from| (from as u8, (bits >> 24) as u8)),
+    }
+}
+
+/// Default recording target, overwritten every session.
+pub fn last_replay_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("OrcWars").join("replays").join("last.orcreplay"))
+}
+
+/// A new game replaces the world, so the recording starts over from its seed.
+fn restart_recording(seed: Res<WorldSeed>, difficulty: Res<Difficulty>, mut recorder: ResMut<ReplayRecorder>) {
+    recorder.replay.seed = *seed;
+    recorder.replay.difficulty = *difficulty;
+    recorder.replay.inputs.clear();
+}
+
+fn record_input(input: Res<PlayerInput>, mut recorder: ResMut<ReplayRecorder>) {
+    recorder.replay.inputs.push(*input);
+}
+
+fn playback_input(mut playback: ResMut<ReplayPlayback>, mut input: ResMut<PlayerInput>) {
+    let tick = playback.tick;
+    *input = playback.replay.inputs.get(tick).copied().unwrap_or_default();
+    playback.tick += 1;
+}
+
+/// The recorded inputs already hold the upgrade that was picked.
+fn resume_after_level_up(mut next_state: ResMut<NextState<GameStatus>>) {
+    next_state.set(GameStatus::Started);
+}
+
+fn update_state_hash(
+    mut hash: ResMut<StateHash>,
+    player_query: Query<(&Transform, &Player)>,
+    enemy_query: Query<(), With<Enemy>>,
+) {
+    // FNV-1a, stable across platforms and compiler versions
+    let mut state: u64 = 0xcbf29ce484222325;
+    let mut feed = |bytes: &[u8]| {
+        for byte in bytes {
+            state ^= *byte as u64;
+            state = state.wrapping_mul(0x100000001b3);
+        }
+    };
+    for (transform, player) in &player_query {
+        feed(&transform.translation.x.to_bits().to_le_bytes());
+        feed(&transform.translation.y.to_bits().to_le_bytes());
+        feed(&player.health.to_le_bytes());
+    }
+    feed(&(enemy_query.iter().len() as u64).to_le_bytes());
+    hash.0 = state;
+}
+
+fn save_recording_on_exit(
+    mut exit_events: EventReader<AppExit>,
+    hash: Res<StateHash>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    if exit_events.read().last().is_none() {
+        return;
+    }
+    recorder.replay.final_hash = hash.0;
+    match recorder.replay.save(&recorder.path) {
+        Ok(()) => info!(target: REPLAY_TARGET, "Saved replay of {} ticks to {}", recorder.replay.inputs.len(), recorder.path.display()),
+        Err(err) => error!(target: REPLAY_TARGET, "Could not save replay to {}: {}", recorder.path.display(), err),
+    }
+}
+
+fn check_playback_finished(
+    mut commands: Commands,
+    playback: Res<ReplayPlayback>,
+    hash: Res<StateHash>,
+    result: Option<Res<ReplayResult>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if result.is_some() || playback.tick < playback.replay.inputs.len() {
+        return;
+    }
+    let expected = playback.replay.final_hash;
+    if hash.0 == expected {
+        info!(target: REPLAY_TARGET, "Replay finished after {} ticks, final state matches", playback.tick);
+        commands.insert_resource(ReplayResult::Matched);
+        exit.send(AppExit::Success);
+    } else {
+        error!(target: REPLAY_TARGET, "Replay diverged: expected state hash {:016x}, got {:016x}", expected, hash.0);
+        commands.insert_resource(ReplayResult::Mismatched { expected, actual: hash.0 });
+        exit.send(AppExit::error());
+    }
+}
@@ -0,0 +1,30 @@
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+/// Seed the whole run is derived from: terrain noise, vegetation and gameplay rolls.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WorldSeed(pub u64);
+
+/// Gameplay RNG. Only use it from `FixedUpdate` systems with a fixed order,
+/// otherwise the draw sequence (and the simulation) stops being reproducible.
+#[derive(Resource, Deref, DerefMut)]
+pub struct GameRng(StdRng);
+
+pub struct RngPlugin;
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        let seed = app.world().get_resource::<WorldSeed>()
+            .copied()
+            .unwrap_or_else(|| WorldSeed(rand::random()));
+        app.insert_resource(seed)
+            .insert_resource(GameRng::from_world_seed(seed));
+    }
+}
+
+impl GameRng {
+    pub fn from_world_seed(seed: WorldSeed) -> Self {
+        Self(StdRng::seed_from_u64(seed.0))
+    }
+}
@@ -0,0 +1,591 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::config::AUTOSAVE_INTERVAL;
+use crate::daynight::GameClock;
+use crate::enemy::{enemy_bundle, Difficulty, Enemy, EnemyState, EnemyType, WaveState};
+use crate::inventory::{pickup_bundle, Inventory, Pickup, PickupLifetime};
+use crate::leveling::{Experience, UpgradeChoices};
+use crate::logging::SAVE_TARGET;
+use crate::player::{Player, PlayerStats};
+use crate::projectile::{Projectile, ProjectilePool};
+use crate::rng::{GameRng, WorldSeed};
+use crate::terrain::{generate_terrain, replace_terrain, MapSize, TerrainEdit, TerrainMap};
+use crate::timestep::Interpolated;
+use crate::weapons::Weapons;
+use crate::weather::WeatherState;
+use crate::GameStatus;
+
+/// Bumped whenever `SaveGame` changes shape. Version 1 files are migrated on
+/// load; anything else is refused.
+pub const SAVE_VERSION: u32 = 2;
+const MANUAL_SLOTS: u8 = 3;
+
+const QUICK_SAVE_KEY: KeyCode = KeyCode::F5;
+const QUICK_LOAD_KEY: KeyCode = KeyCode::F9;
+const PAUSE_KEY: KeyCode = KeyCode::Escape;
+
+#[derive(Default)]
+pub struct SavePlugin {
+    pub headless: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SaveSlot {
+    Auto,
+    Quick,
+    Manual(u8),
+}
+
+/// `E` is how enemy types are written, which only differs in old versions.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SaveGame<E = EnemyType> {
+    pub version: u32,
+    pub seed: u64,
+    #[serde(default)]
+    pub map_size: MapSize,
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    pub terrain_edits: Vec<TerrainEdit>,
+    /// See `TerrainMap::explored_runs`.
+    #[serde(default)]
+    pub explored: Vec<u32>,
+    pub player: PlayerSave,
+    pub enemies: Vec<EnemySave<E>>,
+    pub wave: WaveSave,
+    #[serde(default)]
+    pub clock: GameClock,
+    /// Missing from older saves, which start the weather over.
+    #[serde(default)]
+    pub weather: Option<WeatherState>,
+    #[serde(default)]
+    pub pickups: Vec<PickupSave>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PlayerSave {
+    pub position: (f32, f32),
+    pub health: i32,
+    pub stamina: f32,
+    #[serde(default)]
+    pub inventory: Inventory,
+    #[serde(default)]
+    pub experience: Experience,
+    #[serde(default)]
+    pub stats: PlayerStats,
+    #[serde(default)]
+    pub weapons: Weapons,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PickupSave {
+    pub item: String,
+    pub count: u32,
+    pub position: (f32, f32),
+    /// Seconds left for loot that disappears, `None` for pickups that stay.
+    #[serde(default)]
+    pub expires_in: Option<f32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EnemySave<E = EnemyType> {
+    pub enemy_type: E,
+    pub state: EnemyState,
+    pub position: (f32, f32),
+    pub health: i32,
+    /// Missing from older saves, which count the saved health as full.
+    #[serde(default)]
+    pub max_health: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct WaveSave {
+    pub wave: u32,
+    pub kills: u32,
+    pub elapsed: f32,
+    pub time_in_wave: f32,
+}
+
+/// Enemy types of version 1 saves, from before the enemy registry.
+#[derive(Deserialize, Clone, Copy, Debug)]
+enum LegacyEnemyType {
+    Slave,
+    MiniBoss,
+    Archer,
+    Shaman,
+    Brute,
+}
+
+/// Only the version, to tell how to read the rest of a file.
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Format(String),
+    Version(u32),
+}
+
+#[derive(Event)]
+pub struct SaveGameEvent(pub SaveSlot);
+#[derive(Event)]
+pub struct LoadGameEvent(pub SaveSlot);
+/// Sent once a slot was written successfully.
+#[derive(Event)]
+pub struct GameSaved(pub SaveSlot);
+
+/// Directory save slots are written to. Defaults to the user data dir.
+#[derive(Resource, Clone)]
+pub struct SaveDir(pub PathBuf);
+
+#[derive(Resource, Deref, DerefMut)]
+struct AutosaveTimer(Timer);
+
+#[derive(Component)]
+struct LoadMenu;
+#[derive(Component)]
+enum LoadMenuButton {
+    Resume,
+    MainMenu,
+    Save(SaveSlot),
+    Load(SaveSlot),
+}
+
+const MENU_BACKGROUND: Color = Color::srgba(0.0, 0.0, 0.0, 0.8);
+const BUTTON_COLOR: Color = Color::srgb(0.25, 0.2, 0.15);
+const BUTTON_HOVER_COLOR: Color = Color::srgb(0.4, 0.32, 0.2);
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        if !app.world().contains_resource::<SaveDir>() {
+            let dir = dirs::data_dir().unwrap_or_default().join("OrcWars").join("saves");
+            app.insert_resource(SaveDir(dir));
+        }
+        app.add_event::<SaveGameEvent>()
+            .add_event::<LoadGameEvent>()
+            .add_event::<GameSaved>()
+            .insert_resource(AutosaveTimer(Timer::from_seconds(AUTOSAVE_INTERVAL, TimerMode::Repeating)))
+            .add_systems(Update, autosave.run_if(in_state(GameStatus::Started)))
+            // After a tick, so transforms hold simulated positions rather than interpolated ones
+            .add_systems(FixedPostUpdate, (
+                save_game.run_if(on_event::<SaveGameEvent>()),
+                load_game.run_if(on_event::<LoadGameEvent>()),
+            ).chain());
+
+        if !self.headless {
+            app.add_systems(Update, (
+                quick_save_keys,
+                toggle_pause,
+                load_menu_buttons.run_if(in_state(GameStatus::Paused)),
+                refresh_load_menu.run_if(in_state(GameStatus::Paused).and_then(on_event::<GameSaved>())),
+            ))
+            .add_systems(OnEnter(GameStatus::Paused), spawn_load_menu)
+            .add_systems(OnExit(GameStatus::Paused), despawn_load_menu);
+        }
+    }
+}
+
+impl SavePlugin {
+    /// Save/load events and autosave only: no pause menu or hotkeys.
+    pub fn headless() -> Self {
+        Self { headless: true }
+    }
+}
+
+impl SaveSlot {
+    pub fn all() -> impl Iterator<Item = SaveSlot> {
+        [SaveSlot::Auto, SaveSlot::Quick].into_iter()
+            .chain((1..=MANUAL_SLOTS).map(SaveSlot::Manual))
+    }
+    pub fn file_name(&self) -> String {
+        match self {
+            SaveSlot::Auto => "autosave.ron".to_string(),
+            SaveSlot::Quick => "quicksave.ron".to_string(),
+            SaveSlot::Manual(index) => format!("slot_{}.ron", index),
+        }
+    }
+    pub fn label(&self) -> String {
+        match self {
+            SaveSlot::Auto => "Autosave".to_string(),
+            SaveSlot::Quick => "Quick save".to_string(),
+            SaveSlot::Manual(index) => format!("Slot {}", index),
+        }
+    }
+}
+
+impl SaveDir {
+    pub fn path(&self, slot: SaveSlot) -> PathBuf {
+        self.0.join(slot.file_name())
+    }
+    /// Most recently written slot, used by "Continue".
+    pub fn latest(&self) -> Option<SaveSlot> {
+        SaveSlot::all()
+            .filter_map(|slot| {
+                let modified = std::fs::metadata(self.path(slot)).and_then(|meta| meta.modified()).ok()?;
+                Some((modified, slot))
+            })
+            .max_by_key(|(modified, _): &(SystemTime, SaveSlot)| *modified)
+            .map(|(_, slot)| slot)
+    }
+}
+
+impl SaveGame {
+    pub fn read(path: &Path) -> Result<Self, SaveError> {
+        let text = std::fs::read_to_string(path).map_err(SaveError::Io)?;
+        let format = |err: ron::error::SpannedError| SaveError::Format(err.to_string());
+        let header: SaveHeader = ron::from_str(&text).map_err(format)?;
+        match header.version {
+            SAVE_VERSION => ron::from_str(&text).map_err(format),
+            1 => ron::from_str(&text).map(migrate_v1).map_err(format),
+            version => Err(SaveError::Version(version)),
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), SaveError> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(SaveError::Io)?;
+        }
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|err| SaveError::Format(err.to_string()))?;
+        std::fs::write(path, text).map_err(SaveError::Io)
+    }
+
+    fn summary(&self) -> String {
+        let seconds = self.wave.elapsed as u32;
+        format!("Wave {} - {:02}:{:02} - {} kills", self.wave.wave, seconds / 60, seconds % 60, self.wave.kills)
+    }
+}
+
+/// Version 2 names enemy types by their id in `assets/enemies.ron`. Everything
+/// else it added has defaults.
+fn migrate_v1(save: SaveGame<LegacyEnemyType>) -> SaveGame {
+    let enemies = save.enemies.into_iter()
+        .map(|enemy| EnemySave {
+            enemy_type: EnemyType::new(match enemy.enemy_type {
+                LegacyEnemyType::Slave => "slave",
+                LegacyEnemyType::MiniBoss => "mini_boss",
+                LegacyEnemyType::Archer => "archer",
+                LegacyEnemyType::Shaman => "shaman",
+                LegacyEnemyType::Brute => "brute",
+            }),
+            state: enemy.state,
+            position: enemy.position,
+            health: enemy.health,
+            max_health: enemy.max_health,
+        })
+        .collect();
+    SaveGame {
+        version: SAVE_VERSION,
+        seed: save.seed,
+        map_size: save.map_size,
+        difficulty: save.difficulty,
+        terrain_edits: save.terrain_edits,
+        explored: save.explored,
+        player: save.player,
+        enemies,
+        wave: save.wave,
+        clock: save.clock,
+        weather: save.weather,
+        pickups: save.pickups,
+    }
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(err) => write!(f, "{}", err),
+            SaveError::Format(err) => write!(f, "invalid save file: {}", err),
+            SaveError::Version(version) => write!(f, "save version {} is not supported (expected {})", version, SAVE_VERSION),
+        }
+    }
+}
+
+fn autosave(
+    time: Res<Time>,
+    mut timer: ResMut<AutosaveTimer>,
+    mut events: EventWriter<SaveGameEvent>,
+) {
+    if timer.tick(time.delta()).just_finished() {
+        events.send(SaveGameEvent(SaveSlot::Auto));
+    }
+}
+
+fn save_game(
+    (mut events, mut saved, dir): (EventReader<SaveGameEvent>, EventWriter<GameSaved>, Res<SaveDir>),
+    (seed, map_size, difficulty): (Res<WorldSeed>, Res<MapSize>, Res<Difficulty>),
+    (terrain, wave, clock, weather): (Res<TerrainMap>, Res<WaveState>, Res<GameClock>, Res<WeatherState>),
+    player_query: Query<(&Player, &PlayerStats, &Experience, &Inventory, &Transform)>,
+    weapons_query: Query<&Weapons>,
+    enemy_query: Query<(&Enemy, &EnemyType, &EnemyState, &Transform)>,
+    pickup_query: Query<(&Pickup, &Transform, Option<&PickupLifetime>)>,
+) {
+    let Ok((player, stats, experience, inventory, transform)) = player_query.get_single() else { return; };
+    let Ok(weapons) = weapons_query.get_single() else { return; };
+    let position = transform.translation;
+
+    let save = SaveGame {
+        version: SAVE_VERSION,
+        seed: seed.0,
+        map_size: *map_size,
+        difficulty: *difficulty,
+        terrain_edits: terrain.edits().to_vec(),
+        explored: terrain.explored_runs(),
+        player: PlayerSave {
+            position: (position.x, position.y),
+            health: player.health,
+            stamina: player.stamina,
+            inventory: inventory.clone(),
+            experience: *experience,
+            stats: stats.clone(),
+            weapons: weapons.clone(),
+        },
+        enemies: enemy_query.iter()
+            .map(|(enemy, enemy_type, state, transform)| {
+                let position = transform.translation;
+                EnemySave {
+                    enemy_type: enemy_type.clone(),
+                    state: *state,
+                    position: (position.x, position.y),
+                    health: enemy.health,
+                    max_health: Some(enemy.max_health),
+                }
+            })
+            .collect(),
+        wave: WaveSave {
+            wave: wave.wave,
+            kills: wave.kills,
+            elapsed: wave.elapsed.elapsed_secs(),
+            time_in_wave: wave.time_in_wave(),
+        },
+        clock: *clock,
+        weather: Some(*weather),
+        pickups: pickup_query.iter()
+            .map(|(pickup, transform, lifetime)| PickupSave {
+                item: pickup.item.clone(),
+                count: pickup.count,
+                position: (transform.translation.x, transform.translation.y),
+                expires_in: lifetime.map(|lifetime| lifetime.0.remaining_secs()),
+            })
+            .collect(),
+    };
+
+    for SaveGameEvent(slot) in events.read() {
+        let path = dir.path(*slot);
+        match save.write(&path) {
+            Ok(()) => {
+                info!(target: SAVE_TARGET, "Saved {} to {}", slot.label(), path.display());
+                saved.send(GameSaved(*slot));
+            }
+            Err(err) => error!(target: SAVE_TARGET, "Could not save {}: {}", slot.label(), err),
+        }
+    }
+}
+
+fn load_game(
+    mut commands: Commands,
+    (mut events, dir): (EventReader<LoadGameEvent>, Res<SaveDir>),
+    (mut wave, mut next_state): (ResMut<WaveState>, ResMut<NextState<GameStatus>>),
+    mut player_query: Query<(&mut Player, &mut Transform, Option<&mut Interpolated>)>,
+    mut progress_query: Query<(&mut PlayerStats, &mut Experience, &mut Inventory, &mut Weapons)>,
+    (enemy_query, pickup_query): (Query<Entity, With<Enemy>>, Query<Entity, With<Pickup>>),
+    projectile_query: Query<Entity, With<Projectile>>,
+) {
+    let Some(LoadGameEvent(slot)) = events.read().last() else { return; };
+    let save = match SaveGame::read(&dir.path(*slot)) {
+        Ok(save) => save,
+        Err(err) => {
+            error!(target: SAVE_TARGET, "Could not load {}: {}", slot.label(), err);
+            return;
+        }
+    };
+
+    let seed = WorldSeed(save.seed);
+    let mut terrain = generate_terrain(seed, save.map_size);
+    terrain.apply_edits(&save.terrain_edits);
+    terrain.restore_explored(&save.explored);
+    replace_terrain(&mut commands, terrain);
+    commands.insert_resource(save.map_size);
+    commands.insert_resource(save.difficulty);
+    commands.insert_resource(seed);
+    commands.insert_resource(GameRng::from_world_seed(seed));
+
+    for (mut player, mut transform, interpolated) in &mut player_query {
+        player.health = save.player.health;
+        player.stamina = save.player.stamina;
+        transform.translation.x = save.player.position.0;
+        transform.translation.y = save.player.position.1;
+        if let Some(mut interpolated) = interpolated {
+            interpolated.teleport(transform.translation);
+        }
+    }
+
+    for (mut stats, mut experience, mut inventory, mut weapons) in &mut progress_query {
+        *stats = save.player.stats.clone();
+        *experience = save.player.experience;
+        *inventory = save.player.inventory.clone();
+        *weapons = save.player.weapons.clone();
+    }
+    commands.insert_resource(UpgradeChoices::default());
+    commands.insert_resource(ProjectilePool::default());
+    for entity in enemy_query.iter().chain(&pickup_query).chain(&projectile_query) {
+        commands.entity(entity).despawn_recursive();
+    }
+    for enemy in &save.enemies {
+        let position = Vec2::new(enemy.position.0, enemy.position.1);
+        let max_health = enemy.max_health.unwrap_or(enemy.health);
+        commands.spawn(enemy_bundle(enemy.enemy_type.clone(), enemy.state, position, max_health))
+            .insert(Enemy { health: enemy.health, max_health });
+    }
+    for pickup in &save.pickups {
+        let mut entity = commands.spawn(pickup_bundle(&pickup.item, pickup.count, Vec2::new(pickup.position.0, pickup.position.1)));
+        if let Some(seconds) = pickup.expires_in {
+            entity.insert(PickupLifetime(Timer::from_seconds(seconds, TimerMode::Once)));
+        }
+    }
+
+    wave.restore(save.wave.wave, save.wave.kills, save.wave.elapsed, save.wave.time_in_wave);
+    commands.insert_resource(save.clock);
+    commands.insert_resource(save.weather.unwrap_or_else(|| WeatherState::new(seed)));
+    next_state.set(GameStatus::Started);
+    info!(target: SAVE_TARGET, "Loaded {} ({} enemies)", slot.label(), save.enemies.len());
+}
+
+fn quick_save_keys(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    state: Res<State<GameStatus>>,
+    mut save_events: EventWriter<SaveGameEvent>,
+    mut load_events: EventWriter<LoadGameEvent>,
+) {
+    if keyboard.just_pressed(QUICK_SAVE_KEY) && *state.get() == GameStatus::Started {
+        save_events.send(SaveGameEvent(SaveSlot::Quick));
+    }
+    if keyboard.just_pressed(QUICK_LOAD_KEY) {
+        load_events.send(LoadGameEvent(SaveSlot::Quick));
+    }
+}
+
+fn toggle_pause(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    state: Res<State<GameStatus>>,
+    mut next_state: ResMut<NextState<GameStatus>>,
+) {
+    if !keyboard.just_pressed(PAUSE_KEY) {
+        return;
+    }
+    match state.get() {
+        GameStatus::Started => next_state.set(GameStatus::Paused),
+        GameStatus::Paused => next_state.set(GameStatus::Started),
+        GameStatus::MainMenu | GameStatus::LevelUp => {}
+    }
+}
+
+fn spawn_load_menu(mut commands: Commands, dir: Res<SaveDir>) {
+    let text_style = TextStyle { font_size: 20.0, ..default() };
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            background_color: MENU_BACKGROUND.into(),
+            z_index: ZIndex::Global(50),
+            ..default()
+        },
+        LoadMenu,
+    )).with_children(|parent| {
+        parent.spawn(TextBundle::from_section("Paused", TextStyle { font_size: 36.0, ..default() }));
+        spawn_button(parent, "Resume", LoadMenuButton::Resume, &text_style);
+        spawn_button(parent, "Main menu", LoadMenuButton::MainMenu, &text_style);
+
+        for slot in SaveSlot::all() {
+            let summary = match SaveGame::read(&dir.path(slot)) {
+                Ok(save) => Some(save.summary()),
+                Err(SaveError::Io(err)) if err.kind() == io::ErrorKind::NotFound => None,
+                Err(err) => Some(err.to_string()),
+            };
+            parent.spawn(NodeBundle {
+                style: Style {
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(12.0),
+                    ..default()
+                },
+                ..default()
+            }).with_children(|row| {
+                let label = format!("{}: {}", slot.label(), summary.as_deref().unwrap_or("Empty"));
+                row.spawn(TextBundle::from_section(label, text_style.clone()));
+                if let SaveSlot::Manual(_) = slot {
+                    spawn_button(row, "Save", LoadMenuButton::Save(slot), &text_style);
+                }
+                if summary.is_some() {
+                    spawn_button(row, "Load", LoadMenuButton::Load(slot), &text_style);
+                }
+            });
+        }
+    });
+}
+
+fn spawn_button(parent: &mut ChildBuilder, label: &str, action: LoadMenuButton, text_style: &TextStyle) {
+    parent.spawn((
+        ButtonBundle {
+            style: Style {
+                padding: UiRect::axes(Val::Px(14.0), Val::Px(6.0)),
+                ..default()
+            },
+            background_color: BUTTON_COLOR.into(),
+            ..default()
+        },
+        action,
+    )).with_children(|button| {
+        button.spawn(TextBundle::from_section(label, text_style.clone()));
+    });
+}
+
+fn despawn_load_menu(mut commands: Commands, query: Query<Entity, With<LoadMenu>>) {
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn refresh_load_menu(commands: Commands, query: Query<Entity, With<LoadMenu>>, dir: Res<SaveDir>) {
+    let mut commands = commands;
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+    spawn_load_menu(commands, dir);
+}
+
+fn load_menu_buttons(
+    mut query: Query<(&Interaction, &LoadMenuButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut next_state: ResMut<NextState<GameStatus>>,
+    mut save_events: EventWriter<SaveGameEvent>,
+    mut load_events: EventWriter<LoadGameEvent>,
+) {
+    for (interaction, button, mut color) in &mut query {
+        match interaction {
+            Interaction::Pressed => match button {
+                LoadMenuButton::Resume => next_state.set(GameStatus::Started),
+                LoadMenuButton::MainMenu => next_state.set(GameStatus::MainMenu),
+                LoadMenuButton::Save(slot) => {
+                    save_events.send(SaveGameEvent(*slot));
+                }
+                LoadMenuButton::Load(slot) => {
+                    load_events.send(LoadGameEvent(*slot));
+                }
+            },
+            Interaction::Hovered => *color = BUTTON_HOVER_COLOR.into(),
+            Interaction::None => *color = BUTTON_COLOR.into(),
+        }
+    }
+}
@@ -0,0 +1,384 @@
+use std::io;
+use std::path::PathBuf;
+use bevy::audio::{GlobalVolume, Volume};
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use bevy::window::{PresentMode, PrimaryWindow, WindowMode};
+use serde::{Deserialize, Serialize};
+use crate::config::{HEIGHT, WIDTH};
+use crate::enemy::Difficulty;
+use crate::logging::SETTINGS_TARGET;
+use crate::menu::{menu_root, menu_text, spawn_button, MenuAction, MenuCapture, MenuFocus, MenuScreen, TITLE_FONT_SIZE};
+use crate::player::KeyBindings;
+
+const SETTINGS_FILE: &str = "settings.ron";
+const RESOLUTIONS: [(u32, u32); 5] = [(WIDTH as u32, HEIGHT as u32), (1280, 720), (1600, 900), (1920, 1080), (2560, 1440)];
+const UI_SCALES: [f32; 4] = [0.75, 1.0, 1.25, 1.5];
+const VOLUME_STEP: f32 = 0.1;
+
+/// Needs `MenuPlugin` for the settings screen. Loads `Settings` from the
+/// config dir unless one was inserted already (main does, to size the window).
+pub struct SettingsPlugin;
+
+/// Player options, saved as RON in the platform config dir whenever they change.
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub resolution: (u32, u32),
+    pub window_mode: WindowModeSetting,
+    pub vsync: bool,
+    pub ui_scale: f32,
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub key_bindings: KeyBindings,
+    /// Used for the next new game; a running game keeps its own.
+    pub difficulty: Difficulty,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WindowModeSetting {
+    #[default]
+    Windowed,
+    Borderless,
+    Fullscreen,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SettingKind {
+    Resolution,
+    WindowMode,
+    Vsync,
+    UiScale,
+    MasterVolume,
+    MusicVolume,
+    SfxVolume,
+    Difficulty,
+    Bind(Binding),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Up,
+    Down,
+    Left,
+    Right,
+    Sprint,
+    Interact,
+}
+
+/// Binding waiting for the next key press.
+#[derive(Resource, Default)]
+struct Rebinding(Option<Binding>);
+
+#[derive(Component)]
+struct SettingLabel(SettingKind);
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.world().contains_resource::<Settings>() {
+            app.insert_resource(Settings::load());
+        }
+        app.init_resource::<Rebinding>()
+            .add_systems(Update, (
+                apply_settings.run_if(resource_changed::<Settings>),
+                (
+                    capture_rebinding,
+                    handle_settings_actions,
+                    update_setting_labels,
+                ).chain().run_if(in_state(MenuScreen::Settings)),
+            ))
+            .add_systems(OnEnter(MenuScreen::Settings), spawn_settings_screen)
+            .add_systems(OnExit(MenuScreen::Settings), cancel_rebinding);
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            resolution: RESOLUTIONS[0],
+            window_mode: WindowModeSetting::default(),
+            vsync: true,
+            ui_scale: 1.0,
+            master_volume: 1.0,
+            music_volume: 0.8,
+            sfx_volume: 0.8,
+            key_bindings: KeyBindings::default(),
+            difficulty: Difficulty::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("OrcWars").join(SETTINGS_FILE))
+    }
+
+    /// Falls back to defaults if the file is missing or unreadable. Runs before
+    /// logging is set up, so problems go to stderr.
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else { return Self::default(); };
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Self::default(),
+            Err(err) => {
+                eprintln!("Could not read settings {}: {}", path.display(), err);
+                return Self::default();
+            }
+        };
+        ron::from_str(&text).unwrap_or_else(|err| {
+            eprintln!("Invalid settings file {}, using defaults: {}", path.display(), err);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = Self::path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        std::fs::write(path, text)
+    }
+
+    pub fn apply_to_window(&self, window: &mut Window) {
+        window.resolution.set(self.resolution.0 as f32, self.resolution.1 as f32);
+        window.mode = match self.window_mode {
+            WindowModeSetting::Windowed => WindowMode::Windowed,
+            WindowModeSetting::Borderless => WindowMode::BorderlessFullscreen,
+            WindowModeSetting::Fullscreen => WindowMode::Fullscreen,
+        };
+        window.present_mode = if self.vsync { PresentMode::AutoVsync } else { PresentMode::AutoNoVsync };
+    }
+
+    fn key(&self, binding: Binding) -> KeyCode {
+        let keys = &self.key_bindings;
+        match binding {
+            Binding::Up => keys.up,
+            Binding::Down => keys.down,
+            Binding::Left => keys.left,
+            Binding::Right => keys.right,
+            Binding::Sprint => keys.sprint,
+            Binding::Interact => keys.interact,
+        }
+    }
+
+    fn key_mut(&mut self, binding: Binding) -> &mut KeyCode {
+        let keys = &mut self.key_bindings;
+        match binding {
+            Binding::Up => &mut keys.up,
+            Binding::Down => &mut keys.down,
+            Binding::Left => &mut keys.left,
+            Binding::Right => &mut keys.right,
+            Binding::Sprint => &mut keys.sprint,
+            Binding::Interact => &mut keys.interact,
+        }
+    }
+
+    fn adjust(&mut self, kind: SettingKind, step: i32) {
+        match kind {
+            SettingKind::Resolution => self.resolution = cycle(&RESOLUTIONS, self.resolution, step),
+            SettingKind::WindowMode => self.window_mode = cycle(&WindowModeSetting::ALL, self.window_mode, step),
+            SettingKind::Vsync => self.vsync = !self.vsync,
+            SettingKind::UiScale => self.ui_scale = cycle(&UI_SCALES, self.ui_scale, step),
+            SettingKind::MasterVolume => self.master_volume = step_volume(self.master_volume, step),
+            SettingKind::MusicVolume => self.music_volume = step_volume(self.music_volume, step),
+            SettingKind::SfxVolume => self.sfx_volume = step_volume(self.sfx_volume, step),
+            SettingKind::Difficulty => self.difficulty = cycle(&Difficulty::ALL, self.difficulty, step),
+            SettingKind::Bind(_) => {}
+        }
+    }
+}
+
+impl WindowModeSetting {
+    pub const ALL: [WindowModeSetting; 3] = [WindowModeSetting::Windowed, WindowModeSetting::Borderless, WindowModeSetting::Fullscreen];
+
+    fn label(&self) -> &'static str {
+        match self {
+            WindowModeSetting::Windowed => "Windowed",
+            WindowModeSetting::Borderless => "Borderless",
+            WindowModeSetting::Fullscreen => "Fullscreen",
+        }
+    }
+}
+
+impl SettingKind {
+    fn label(&self, settings: &Settings, rebinding: Option<Binding>) -> String {
+        let percent = |value: f32| format!("{}%", (value * 100.0).round() as i32);
+        match self {
+            SettingKind::Resolution => format!("Resolution: {}x{}", settings.resolution.0, settings.resolution.1),
+            SettingKind::WindowMode => format!("Window: {}", settings.window_mode.label()),
+            SettingKind::Vsync => format!("VSync: {}", if settings.vsync { "On" } else { "Off" }),
+            SettingKind::UiScale => format!("UI scale: {}", percent(settings.ui_scale)),
+            SettingKind::MasterVolume => format!("Master volume: {}", percent(settings.master_volume)),
+            SettingKind::MusicVolume => format!("Music volume: {}", percent(settings.music_volume)),
+            SettingKind::SfxVolume => format!("SFX volume: {}", percent(settings.sfx_volume)),
+            SettingKind::Difficulty => format!("Difficulty: {}", settings.difficulty.label()),
+            SettingKind::Bind(binding) if rebinding == Some(*binding) => format!("{}: press a key", binding.label()),
+            SettingKind::Bind(binding) => format!("{}: {}", binding.label(), key_name(settings.key(*binding))),
+        }
+    }
+}
+
+impl Binding {
+    const ALL: [Binding; 6] = [Binding::Up, Binding::Down, Binding::Left, Binding::Right, Binding::Sprint, Binding::Interact];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Binding::Up => "Move up",
+            Binding::Down => "Move down",
+            Binding::Left => "Move left",
+            Binding::Right => "Move right",
+            Binding::Sprint => "Sprint",
+            Binding::Interact => "Interact",
+        }
+    }
+}
+
+fn cycle<T: Copy + PartialEq>(options: &[T], current: T, step: i32) -> T {
+    let index = options.iter().position(|option| *option == current).unwrap_or_default();
+    options[(index as i32 + step).rem_euclid(options.len() as i32) as usize]
+}
+
+fn step_volume(volume: f32, step: i32) -> f32 {
+    ((volume + step as f32 * VOLUME_STEP) / VOLUME_STEP).round().clamp(0.0, 1.0 / VOLUME_STEP) * VOLUME_STEP
+}
+
+fn key_name(key: KeyCode) -> String {
+    let name = format!("{:?}", key);
+    name.strip_prefix("Key").or_else(|| name.strip_prefix("Digit")).unwrap_or(&name).to_string()
+}
+
+fn apply_settings(
+    settings: Res<Settings>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut ui_scale: ResMut<UiScale>,
+    mut global_volume: ResMut<GlobalVolume>,
+    mut key_bindings: ResMut<KeyBindings>,
+) {
+    for mut window in &mut windows {
+        settings.apply_to_window(&mut window);
+    }
+    ui_scale.0 = settings.ui_scale;
+    // Only affects sounds started from now on
+    global_volume.volume = Volume::new(settings.master_volume);
+    *key_bindings = settings.key_bindings;
+
+    if settings.is_added() {
+        return;
+    }
+    match settings.save() {
+        Ok(()) => debug!(target: SETTINGS_TARGET, "Saved settings"),
+        Err(err) => error!(target: SETTINGS_TARGET, "Could not save settings: {}", err),
+    }
+}
+
+fn spawn_settings_screen(mut commands: Commands, mut focus: ResMut<MenuFocus>, settings: Res<Settings>) {
+    focus.0 = 0;
+    let general = [
+        SettingKind::Resolution,
+        SettingKind::WindowMode,
+        SettingKind::Vsync,
+        SettingKind::UiScale,
+        SettingKind::MasterVolume,
+        SettingKind::MusicVolume,
+        SettingKind::SfxVolume,
+        SettingKind::Difficulty,
+    ];
+    let column = || NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(8.0),
+            ..default()
+        },
+        ..default()
+    };
+
+    commands.spawn(menu_root(MenuScreen::Settings)).with_children(|parent| {
+        parent.spawn(menu_text("Settings", TITLE_FONT_SIZE));
+        parent.spawn(NodeBundle {
+            style: Style {
+                column_gap: Val::Px(24.0),
+                ..default()
+            },
+            ..default()
+        }).with_children(|columns| {
+            columns.spawn(column()).with_children(|column| {
+                for (order, kind) in general.into_iter().enumerate() {
+                    let label = kind.label(&settings, None);
+                    spawn_button(column, order, label, MenuAction::Setting(kind, 1), SettingLabel(kind));
+                }
+            });
+            columns.spawn(column()).with_children(|column| {
+                for (index, binding) in Binding::ALL.into_iter().enumerate() {
+                    let kind = SettingKind::Bind(binding);
+                    let label = kind.label(&settings, None);
+                    spawn_button(column, general.len() + index, label, MenuAction::Setting(kind, 1), SettingLabel(kind));
+                }
+                spawn_button(column, general.len() + Binding::ALL.len(), "Back", MenuAction::Back, ());
+            });
+        });
+        parent.spawn(menu_text("Left/Right to change, Enter to rebind a key, Escape to cancel", TITLE_FONT_SIZE * 0.3));
+    });
+}
+
+fn handle_settings_actions(
+    mut actions: EventReader<MenuAction>,
+    mut settings: ResMut<Settings>,
+    mut rebinding: ResMut<Rebinding>,
+    mut capture: ResMut<MenuCapture>,
+) {
+    for action in actions.read() {
+        match action {
+            MenuAction::Setting(SettingKind::Bind(binding), _) => {
+                rebinding.0 = Some(*binding);
+                capture.0 = true;
+            }
+            MenuAction::Setting(kind, step) => settings.adjust(*kind, *step),
+            _ => {}
+        }
+    }
+}
+
+fn capture_rebinding(
+    mut key_events: EventReader<KeyboardInput>,
+    mut keyboard: ResMut<ButtonInput<KeyCode>>,
+    mut settings: ResMut<Settings>,
+    mut rebinding: ResMut<Rebinding>,
+    mut capture: ResMut<MenuCapture>,
+) {
+    // Always drain, so the press that started the rebind is not taken as the new key
+    let pressed = key_events.read()
+        .filter(|event| event.state == ButtonState::Pressed)
+        .map(|event| event.key_code)
+        .last();
+    let (Some(binding), Some(key)) = (rebinding.0, pressed) else { return; };
+
+    if key != KeyCode::Escape {
+        *settings.key_mut(binding) = key;
+    }
+    // Keep the menu from also treating this press as navigation
+    keyboard.clear_just_pressed(key);
+    rebinding.0 = None;
+    capture.0 = false;
+}
+
+fn cancel_rebinding(mut rebinding: ResMut<Rebinding>, mut capture: ResMut<MenuCapture>) {
+    rebinding.0 = None;
+    capture.0 = false;
+}
+
+fn update_setting_labels(
+    settings: Res<Settings>,
+    rebinding: Res<Rebinding>,
+    mut query: Query<(&SettingLabel, &mut Text)>,
+) {
+    if !settings.is_changed() && !rebinding.is_changed() {
+        return;
+    }
+    for (SettingLabel(kind), mut text) in &mut query {
+        text.sections[0].value = kind.label(&settings, rebinding.0);
+    }
+}
@@ -0,0 +1,75 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use crate::config::{ENEMY_RADIUS, SPATIAL_CELL_SIZE};
+use crate::enemy::{EnemyDatabase, EnemyType};
+
+/// Enemy positions and body sizes bucketed into square cells, rebuilt every
+/// tick, so weapons can ask what is near a point without walking every enemy.
+#[derive(Resource, Default)]
+pub struct EnemyGrid {
+    cells: HashMap<(i32, i32), Vec<Body>>,
+    /// Largest body in the grid, so searches reach bodies centred in a further cell.
+    max_radius: f32,
+}
+
+/// An enemy in the grid and the radius of its body.
+struct Body {
+    entity: Entity,
+    position: Vec2,
+    radius: f32,
+}
+
+impl EnemyGrid {
+    fn cell(position: Vec2) -> (i32, i32) {
+        let cell = (position / SPATIAL_CELL_SIZE).floor();
+        (cell.x as i32, cell.y as i32)
+    }
+
+    pub fn insert(&mut self, entity: Entity, position: Vec2, radius: f32) {
+        self.cells.entry(Self::cell(position)).or_default().push(Body { entity, position, radius });
+        self.max_radius = self.max_radius.max(radius);
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.max_radius = 0.0;
+    }
+
+    /// Enemies in the cells overlapping the square around `position`, in a fixed order.
+    fn around(&self, position: Vec2, radius: f32) -> impl Iterator<Item = &Body> {
+        let (min_x, min_y) = Self::cell(position - Vec2::splat(radius));
+        let (max_x, max_y) = Self::cell(position + Vec2::splat(radius));
+        (min_x..=max_x)
+            .flat_map(move |x| (min_y..=max_y).map(move |y| (x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+    }
+
+    /// Closest enemy no further than `max_distance`.
+    pub fn nearest(&self, position: Vec2, max_distance: f32) -> Option<(Entity, Vec2)> {
+        self.around(position, max_distance)
+            .map(|body| (body.entity, body.position, body.position.distance_squared(position)))
+            .filter(|(_, _, distance)| *distance <= max_distance * max_distance)
+            .min_by(|a, b| a.2.total_cmp(&b.2))
+            .map(|(entity, at, _)| (entity, at))
+    }
+
+    /// Every enemy whose body overlaps the circle of `radius` around `position`.
+    pub fn within(&self, position: Vec2, radius: f32) -> impl Iterator<Item = Entity> + '_ {
+        self.around(position, radius + self.max_radius)
+            .filter(move |body| body.position.distance(position) <= radius + body.radius)
+            .map(|body| body.entity)
+    }
+}
+
+pub(crate) fn index_enemies(
+    mut grid: ResMut<EnemyGrid>,
+    enemies: Res<EnemyDatabase>,
+    query: Query<(Entity, &Transform, &EnemyType)>,
+) {
+    grid.clear();
+    for (entity, transform, enemy_type) in &query {
+        let radius = enemies.get(enemy_type).map_or(ENEMY_RADIUS, |def| def.radius);
+        grid.insert(entity, transform.translation.truncate(), radius);
+    }
+}
@@ -3,20 +3,111 @@ use bevy::math::{vec3};
 use bevy::prelude::*;
 use bevy::utils::HashSet;
 use noise::{NoiseFn, Perlin};
-use rand::{Rng, thread_rng};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
 use crate::assets_loader::{TextureGameAssets};
+use crate::logging::TERRAIN_TARGET;
+use crate::rng::WorldSeed;
 use crate::config::{ANIMATION_INTERVAL, MAP_COLUMNS, MAP_OFFSET, MAP_ROWS, MAP_SCALE_FACTOR};
+use crate::player::Player;
+use crate::timestep::Interpolated;
+use crate::weather::WeatherState;
 
-pub struct TerrainPlugin;
+#[derive(Default)]
+pub struct TerrainPlugin {
+    pub headless: bool,
+}
+
+#[derive(Clone,Copy,Default,PartialEq,Eq,Debug)]
+pub enum Biome {
+    #[default]
+    Sand,
+    Grass,
+    DarkForest,
+    ShallowWater,
+    DeepWater,
+}
+
+/// What a vegetation detail from `nature.png` is, decided by its atlas index.
+#[derive(Clone,Copy,PartialEq,Eq,Hash,Debug)]
+pub enum VegetationKind {
+    Tree,
+    Bush,
+    Rock,
+    Flower,
+}
+
+/// How a tile affects whoever moves over it.
+#[derive(Clone,Copy,PartialEq,Debug)]
+pub struct MovementModifiers {
+    pub speed: f32,
+    /// Scales the stamina spent sprinting.
+    pub stamina_drain: f32,
+    /// Shallow water, drawn as wading.
+    pub wading: bool,
+    /// Deep water: no sprinting, and stamina drains instead of recovering.
+    pub swimming: bool,
+}
+
+#[derive(Clone,Copy,Default,Debug)]
+pub struct Tile {
+    pub biome: Biome,
+    pub vegetation: Option<usize>,
+}
+
+/// World size picked when starting a new game. `Normal` is `MAP_ROWS` x `MAP_COLUMNS`.
+#[derive(Resource,Clone,Copy,Default,PartialEq,Eq,Debug,Serialize,Deserialize)]
+pub enum MapSize {
+    Small,
+    #[default]
+    Normal,
+    Large,
+}
+
+/// A tile whose vegetation no longer matches what the seed generates.
+#[derive(Clone,Copy,Debug,PartialEq,Serialize,Deserialize)]
+pub struct TerrainEdit {
+    pub x: i32,
+    pub y: i32,
+    pub vegetation: Option<usize>,
+}
+
+#[derive(Resource)]
+pub struct TerrainMap {
+    pub rows: usize,
+    pub columns: usize,
+    tiles: Vec<Tile>,
+    edits: Vec<TerrainEdit>,
+    explored: Vec<bool>,
+}
+
+/// Sent whenever `TerrainMap` is replaced by a freshly generated one.
+#[derive(Event)]
+pub struct TerrainGenerated;
+
+/// Sent after the vegetation on a tile was cleared, so its sprite goes too.
+#[derive(Event)]
+pub struct VegetationRemoved {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// Colour of a simulated sprite on land. Water tints are multiplied onto it.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct BaseTint(pub Color);
+
+#[derive(Component)]
+struct TerrainSprite;
+#[derive(Component)]
+struct VegetationSprite(i32, i32);
 
 #[derive(Component, Deref, DerefMut)]
 struct AnimationTimer(Timer);
 struct Bioma {
     pos:(i32,i32),
     map_texture: usize,
-    z_index: i32,
     water_type: WaterType,
-    is_water:bool,
 }
 #[derive(Component,Default,PartialEq,Debug,Clone)]
 enum WaterType {
@@ -26,34 +117,275 @@ enum WaterType {
     None
 }
 
+impl TerrainMap {
+    fn new(rows: usize, columns: usize) -> Self {
+        Self {
+            rows,
+            columns,
+            tiles: vec![Tile::default(); rows * columns],
+            edits: Vec::new(),
+            explored: vec![false; rows * columns],
+        }
+    }
+    pub fn set_vegetation(&mut self, x: i32, y: i32, vegetation: Option<usize>) {
+        if self.tile(x, y).is_none() {
+            return;
+        }
+        self.tile_mut(x, y).vegetation = vegetation;
+        self.edits.retain(|edit| (edit.x, edit.y) != (x, y));
+        self.edits.push(TerrainEdit { x, y, vegetation });
+    }
+    pub fn edits(&self) -> &[TerrainEdit] {
+        &self.edits
+    }
+    pub fn apply_edits(&mut self, edits: &[TerrainEdit]) {
+        for edit in edits {
+            self.set_vegetation(edit.x, edit.y, edit.vegetation);
+        }
+    }
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || y < 0 || x as usize >= self.rows || y as usize >= self.columns {
+            return None;
+        }
+        Some(x as usize * self.columns + y as usize)
+    }
+    pub fn tile(&self, x: i32, y: i32) -> Option<&Tile> {
+        self.index(x, y).map(|index| &self.tiles[index])
+    }
+    fn tile_mut(&mut self, x: i32, y: i32) -> &mut Tile {
+        &mut self.tiles[x as usize * self.columns + y as usize]
+    }
+    pub fn is_explored(&self, x: i32, y: i32) -> bool {
+        self.index(x, y).is_some_and(|index| self.explored[index])
+    }
+    /// Exploration as run lengths over the tiles, alternating unexplored and
+    /// explored and starting with unexplored. Compact enough for save files.
+    pub fn explored_runs(&self) -> Vec<u32> {
+        let mut runs = vec![0];
+        let mut current = false;
+        for &explored in &self.explored {
+            if explored != current {
+                runs.push(0);
+                current = explored;
+            }
+            *runs.last_mut().unwrap() += 1;
+        }
+        runs
+    }
+    pub fn restore_explored(&mut self, runs: &[u32]) {
+        let mut index = 0;
+        for (run, &length) in runs.iter().enumerate() {
+            let end = (index + length as usize).min(self.explored.len());
+            self.explored[index..end].fill(run % 2 == 1);
+            index = end;
+        }
+    }
+    /// Marks every tile within `radius` of `center` as explored.
+    /// Returns whether any of them was new.
+    pub fn reveal(&mut self, center: (i32, i32), radius: i32) -> bool {
+        let mut revealed = false;
+        for x in center.0 - radius..=center.0 + radius {
+            for y in center.1 - radius..=center.1 + radius {
+                let (dx, dy) = (x - center.0, y - center.1);
+                if dx * dx + dy * dy > radius * radius {
+                    continue;
+                }
+                if let Some(index) = self.index(x, y) {
+                    revealed |= !self.explored[index];
+                    self.explored[index] = true;
+                }
+            }
+        }
+        revealed
+    }
+    pub fn get(&self, x: i32, y: i32) -> Option<Biome> {
+        self.tile(x, y).map(|tile| tile.biome)
+    }
+    pub fn tiles(&self) -> impl Iterator<Item = ((i32, i32), &Tile)> {
+        self.tiles.iter().enumerate().map(|(index, tile)| {
+            (((index / self.columns) as i32, (index % self.columns) as i32), tile)
+        })
+    }
+    pub fn biome_at(&self, world_pos: Vec2) -> Option<Biome> {
+        let (x, y) = world_to_grid(world_pos);
+        self.get(x, y)
+    }
+    /// Whether a tree or rock stands on the tile under `world_pos`.
+    pub fn is_blocked(&self, world_pos: Vec2) -> bool {
+        let (x, y) = world_to_grid(world_pos);
+        self.tile(x, y)
+            .and_then(|tile| tile.vegetation_kind())
+            .is_some_and(|kind| kind.blocks_movement())
+    }
+    /// Modifiers of the tile under `world_pos`. Off the map nothing changes.
+    pub fn movement_at(&self, world_pos: Vec2) -> MovementModifiers {
+        self.biome_at(world_pos).map(|biome| biome.movement()).unwrap_or_default()
+    }
+}
+
+impl Default for MovementModifiers {
+    fn default() -> Self {
+        Self { speed: 1.0, stamina_drain: 1.0, wading: false, swimming: false }
+    }
+}
+
+impl Tile {
+    pub fn vegetation_kind(&self) -> Option<VegetationKind> {
+        self.vegetation.map(VegetationKind::from_index)
+    }
+}
+
+impl VegetationKind {
+    pub fn from_index(index: usize) -> Self {
+        match index {
+            3 | 7 | 8 => VegetationKind::Tree,
+            5 | 13 => VegetationKind::Bush,
+            12 => VegetationKind::Flower,
+            _ => VegetationKind::Rock,
+        }
+    }
+    pub fn label(&self) -> &'static str {
+        match self {
+            VegetationKind::Tree => "Tree",
+            VegetationKind::Bush => "Bush",
+            VegetationKind::Rock => "Rock",
+            VegetationKind::Flower => "Flower",
+        }
+    }
+    /// Hits it takes to chop or break.
+    pub fn max_health(&self) -> i32 {
+        match self {
+            VegetationKind::Tree => 4,
+            VegetationKind::Bush => 2,
+            VegetationKind::Rock => 5,
+            VegetationKind::Flower => 1,
+        }
+    }
+    pub fn blocks_movement(&self) -> bool {
+        matches!(self, VegetationKind::Tree | VegetationKind::Rock)
+    }
+}
+
+impl Biome {
+    pub fn movement(&self) -> MovementModifiers {
+        let (speed, stamina_drain) = match self {
+            Biome::Sand => (0.9, 1.1),
+            Biome::Grass => (1.0, 1.0),
+            Biome::DarkForest => (0.8, 1.25),
+            Biome::ShallowWater => (0.65, 1.5),
+            Biome::DeepWater => (0.4, 1.0),
+        };
+        MovementModifiers {
+            speed,
+            stamina_drain,
+            wading: *self == Biome::ShallowWater,
+            swimming: *self == Biome::DeepWater,
+        }
+    }
+    fn is_water(&self) -> bool {
+        matches!(self, Biome::ShallowWater | Biome::DeepWater)
+    }
+    fn atlas_index(&self) -> usize {
+        match self {
+            Biome::Sand => 0,
+            Biome::Grass => 1,
+            Biome::DarkForest => 2,
+            Biome::DeepWater => 0,
+            Biome::ShallowWater => 4,
+        }
+    }
+    fn water_type(&self) -> WaterType {
+        match self {
+            Biome::DeepWater => WaterType::Deep,
+            Biome::ShallowWater => WaterType::Normal,
+            _ => WaterType::None,
+        }
+    }
+}
+
+impl MapSize {
+    pub const ALL: [MapSize; 3] = [MapSize::Small, MapSize::Normal, MapSize::Large];
+
+    pub fn dimensions(&self) -> (usize, usize) {
+        match self {
+            MapSize::Small => (MAP_ROWS * 3 / 5, MAP_COLUMNS * 3 / 5),
+            MapSize::Normal => (MAP_ROWS, MAP_COLUMNS),
+            MapSize::Large => (MAP_ROWS * 7 / 5, MAP_COLUMNS * 7 / 5),
+        }
+    }
+    pub fn label(&self) -> &'static str {
+        match self {
+            MapSize::Small => "Small",
+            MapSize::Normal => "Normal",
+            MapSize::Large => "Large",
+        }
+    }
+}
+
+impl TerrainPlugin {
+    /// Generates the `TerrainMap` without spawning any tile sprites.
+    pub fn headless() -> Self {
+        Self { headless: true }
+    }
+}
+
 impl Bioma {
-    fn new(position:(i32,i32), map_texture:usize, z_index:i32,water_type: Option<WaterType>,is_water: Option<bool>) -> Self{
+    fn biome(&self) -> Biome {
+        match (&self.water_type, self.map_texture) {
+            (WaterType::Deep, _) => Biome::DeepWater,
+            (WaterType::Normal, _) => Biome::ShallowWater,
+            (WaterType::None, 1) => Biome::Grass,
+            (WaterType::None, 2) => Biome::DarkForest,
+            _ => Biome::Sand,
+        }
+    }
+
+    fn new(position:(i32,i32), map_texture:usize, water_type: Option<WaterType>) -> Self{
         Self{
             pos:position,
             map_texture,
-            z_index,
             water_type: water_type.unwrap_or_default(),
-            is_water: is_water.unwrap_or(false),
         }
     }
 }
+const WADING_TINT: Color = Color::srgb(0.75, 0.85, 1.0);
+const SWIMMING_TINT: Color = Color::srgb(0.45, 0.6, 0.9);
 const TILE_WIDTH:u32 = 32; // texture pixel width
 const TILE_HEIGHT:u32 = 32; // texture pixel height
 
 impl Plugin for TerrainPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(PostStartup, terrain_setup)
-            .add_systems(Update,animate_sprite);
+        app.add_event::<TerrainGenerated>()
+            .add_event::<VegetationRemoved>()
+            .init_resource::<MapSize>()
+            .add_systems(PostStartup, terrain_setup)
+            .add_systems(FixedPostUpdate, reveal_around_player.run_if(resource_exists::<TerrainMap>));
+        if !self.headless {
+            app.add_systems(Update,(
+                spawn_terrain_sprites.run_if(on_event::<TerrainGenerated>()),
+                despawn_removed_vegetation.run_if(on_event::<VegetationRemoved>()),
+                animate_sprite,
+                tint_sprites_in_water.run_if(resource_exists::<TerrainMap>),
+            ));
+        }
     }
 }
 
 fn terrain_setup(
     mut commands: Commands,
-    texture_handler: Res<TextureGameAssets>,
-    mut texture_atlas_layout: ResMut<Assets<TextureAtlasLayout>>,
+    seed: Res<WorldSeed>,
+    map_size: Res<MapSize>,
+    mut generated: EventWriter<TerrainGenerated>,
 ){
+    commands.insert_resource(generate_terrain(*seed, *map_size));
+    generated.send(TerrainGenerated);
+}
+
+pub fn generate_terrain(seed: WorldSeed, map_size: MapSize) -> TerrainMap {
+    let (rows, columns) = map_size.dimensions();
 
-    let mut random_seed = thread_rng();
+    // Own generator so the map only depends on the seed, not on gameplay draws
+    let mut random_seed = StdRng::seed_from_u64(seed.0);
 
     let perlin = Perlin::new(random_seed.gen());
 
@@ -61,8 +393,8 @@ fn terrain_setup(
     let mut vege = Vec::new();
     let mut occupied_pos = HashSet::new();
 
-    for x in 0..MAP_ROWS {
-        for y in 0..MAP_COLUMNS {
+    for x in 0..rows {
+        for y in 0..columns {
 
             let pos = (x as i32,y as i32);
 
@@ -79,119 +411,166 @@ fn terrain_setup(
 
 
             if noise >= 0.31  && noise < 0.36{
-                let rnd = thread_rng().gen_range(0.0..1.0);
+                let rnd = random_seed.gen_range(0.0..1.0);
                 if rnd > 0.8{
-                    let rnd = thread_rng().gen_range(0..=2);
-                    vege.push(Bioma::new((pos),rnd,0,None,None));
+                    let rnd = random_seed.gen_range(0..=2);
+                    vege.push(Bioma::new(pos,rnd,None));
                 }
-                biomas.push(Bioma::new(pos, 0, -1, Some(WaterType::Deep),Some(true)));
+                biomas.push(Bioma::new(pos, 0, Some(WaterType::Deep)));
                 occupied_pos.insert(pos);
             }
             if noise >= 0.23 && noise < 0.31 {
-                let rnd = thread_rng().gen_range(0.0..1.0);
+                let rnd = random_seed.gen_range(0.0..1.0);
                 if rnd > 0.8{
-                    let rnd = thread_rng().gen_range(0..=2);
-                    vege.push(Bioma::new((pos),rnd,0,None,None));
+                    let rnd = random_seed.gen_range(0..=2);
+                    vege.push(Bioma::new(pos,rnd,None));
                 }
-                biomas.push(Bioma::new(pos, 4, -1, Some(WaterType::Normal),Some(true)));
+                biomas.push(Bioma::new(pos, 4, Some(WaterType::Normal)));
                 occupied_pos.insert(pos);
             }
             if noise >= 0.36 && noise < 0.5 {
-                let rnd = thread_rng().gen_range(0.0..1.0);
+                let rnd = random_seed.gen_range(0.0..1.0);
                 if rnd > 0.8{
-                    let rnd = thread_rng().gen_range(4..=13);
-                    vege.push(Bioma::new((pos),rnd,0,None,None));
+                    let rnd = random_seed.gen_range(4..=13);
+                    vege.push(Bioma::new(pos,rnd,None));
                 }
-                biomas.push(Bioma::new((x as i32, y as i32), 1, -1, None, None));
+                biomas.push(Bioma::new((x as i32, y as i32), 1, None));
                 occupied_pos.insert(pos);
             }
             if noise >= 0.5 && noise < 0.64 {
-                let rnd = thread_rng().gen_range(0.0..1.0);
+                let rnd = random_seed.gen_range(0.0..1.0);
                 if rnd > 0.8{
-                    let rnd = thread_rng().gen_range(14..=15);
-                    vege.push(Bioma::new(pos,rnd,0,None,None));
+                    let rnd = random_seed.gen_range(14..=15);
+                    vege.push(Bioma::new(pos,rnd,None));
                 }
-                biomas.push(Bioma::new((x as i32, y as i32), 2, -1,None,None));
+                biomas.push(Bioma::new((x as i32, y as i32), 2, None));
                 occupied_pos.insert(pos);
             }
             if noise >= 0.64{
-                let rnd = thread_rng().gen_range(0.0..1.0);
+                let rnd = random_seed.gen_range(0.0..1.0);
                 if rnd > 0.8{
-                    let rnd = thread_rng().gen_range(14..=15);
-                    vege.push(Bioma::new(pos,rnd,0,None,None));
+                    let rnd = random_seed.gen_range(14..=15);
+                    vege.push(Bioma::new(pos,rnd,None));
                 }
-                biomas.push(Bioma::new((x as i32, y as i32), 2, -1,None,None));
+                biomas.push(Bioma::new((x as i32, y as i32), 2, None));
                 occupied_pos.insert(pos);
             }
             if noise < 0.23{
-                let rnd = thread_rng().gen_range(0.0..1.0);
+                let rnd = random_seed.gen_range(0.0..1.0);
                 if rnd > 0.7{
-                    let rnd = thread_rng().gen_range(4..=13);
-                    vege.push(Bioma::new(pos,rnd,0,None,None));
+                    let rnd = random_seed.gen_range(4..=13);
+                    vege.push(Bioma::new(pos,rnd,None));
                 }
-                biomas.push(Bioma::new((x as i32, y as i32), 0, -1, None, None));
+                biomas.push(Bioma::new((x as i32, y as i32), 0, None));
                 occupied_pos.insert(pos);
             }
         }
     }
-    for bioma in biomas.iter(){
+    let mut terrain_map = TerrainMap::new(rows, columns);
+    for bioma in biomas.iter() {
+        terrain_map.tile_mut(bioma.pos.0, bioma.pos.1).biome = bioma.biome();
+    }
+    for detail in vege.iter() {
+        terrain_map.tile_mut(detail.pos.0, detail.pos.1).vegetation = Some(detail.map_texture);
+    }
+    info!(target: TERRAIN_TARGET, "Generated {}x{} map with {} vegetation details", rows, columns, vege.len());
+    terrain_map
+}
+
+fn reveal_around_player(
+    mut terrain: ResMut<TerrainMap>,
+    weather: Res<WeatherState>,
+    query: Query<&Transform, With<Player>>,
+) {
+    for transform in &query {
+        let center = world_to_grid(transform.translation.truncate());
+        // Only flag the map as changed when something new was uncovered
+        if terrain.bypass_change_detection().reveal(center, weather.vision_radius()) {
+            terrain.set_changed();
+        }
+    }
+}
 
-        let (x, y) = bioma.pos;
-        let (x, y) = grid_to_world(x as f32, y as f32);
+/// Swaps in a new map, e.g. for a new game or a loaded save, and rebuilds its sprites.
+pub fn replace_terrain(commands: &mut Commands, terrain: TerrainMap) {
+    commands.insert_resource(terrain);
+    // Sent through commands so the sprites are rebuilt from the new map, not the old one
+    commands.add(|world: &mut World| {
+        world.send_event(TerrainGenerated);
+    });
+}
 
-        if bioma.is_water == true {
+fn spawn_terrain_sprites(
+    mut commands: Commands,
+    terrain: Res<TerrainMap>,
+    old_sprites: Query<Entity, With<TerrainSprite>>,
+    texture_handler: Res<TextureGameAssets>,
+    mut texture_atlas_layout: ResMut<Assets<TextureAtlasLayout>>,
+){
+    let water_layout = texture_atlas_layout.add(TextureAtlasLayout::from_grid(UVec2 { x: TILE_WIDTH, y: TILE_HEIGHT }, 3, 3, None, None));
+    let terrain_layout = texture_atlas_layout.add(TextureAtlasLayout::from_grid(UVec2 { x: TILE_WIDTH, y: TILE_HEIGHT }, 3, 1, None, None));
+    let details_layout = texture_atlas_layout.add(TextureAtlasLayout::from_grid(UVec2 { x: TILE_WIDTH, y: TILE_HEIGHT }, 4, 4, None, None));
+
+    for entity in &old_sprites {
+        commands.entity(entity).despawn();
+    }
 
-            let layout = TextureAtlasLayout::from_grid(UVec2 { x: TILE_WIDTH, y: TILE_HEIGHT }, 3, 3, None, None);
-            let texture_atlas_layout = texture_atlas_layout.add(layout);
+    for ((grid_x, grid_y), tile) in terrain.tiles() {
+        let (x, y) = grid_to_world(grid_x as f32, grid_y as f32);
 
-            let texture = texture_handler.map_handler.clone();
+        if tile.biome.is_water() {
             commands.spawn(
                 (SpriteBundle {
-                    transform: Transform::from_translation(vec3(x, y, bioma.z_index as f32)).with_scale(Vec3::splat(MAP_SCALE_FACTOR)),
-                    texture: texture.unwrap(),
+                    transform: Transform::from_translation(vec3(x, y, -1.0)).with_scale(Vec3::splat(MAP_SCALE_FACTOR)),
+                    texture: texture_handler.map_handler.clone().unwrap(),
                     ..default()
                 },
                  TextureAtlas {
-                     layout: texture_atlas_layout.clone(),
-                     index: bioma.map_texture,
-                 }, AnimationTimer(Timer::from_seconds(ANIMATION_INTERVAL, TimerMode::Repeating)), bioma.water_type.clone())
+                     layout: water_layout.clone(),
+                     index: tile.biome.atlas_index(),
+                 }, AnimationTimer(Timer::from_seconds(ANIMATION_INTERVAL, TimerMode::Repeating)), tile.biome.water_type(), TerrainSprite)
             );
-        }else {
-            let layout = TextureAtlasLayout::from_grid(UVec2 { x: TILE_WIDTH, y: TILE_HEIGHT }, 3, 1, None, None);let texture_atlas_layout = texture_atlas_layout.add(layout);
+        } else {
+            commands.spawn(
+                (SpriteBundle {
+                    transform: Transform::from_translation(vec3(x, y, -1.0)).with_scale(Vec3::splat(MAP_SCALE_FACTOR)),
+                    texture: texture_handler.terrain_handler.clone().unwrap(),
+                    ..default()
+                },
+                 TextureAtlas {
+                     layout: terrain_layout.clone(),
+                     index: tile.biome.atlas_index(),
+                 }, AnimationTimer(Timer::from_seconds(ANIMATION_INTERVAL, TimerMode::Repeating)), TerrainSprite)
+            );
+        }
 
-            let texture = texture_handler.terrain_handler.clone();
+        if let Some(detail) = tile.vegetation {
             commands.spawn(
                 (SpriteBundle {
-                    transform: Transform::from_translation(vec3(x, y, bioma.z_index as f32)).with_scale(Vec3::splat(MAP_SCALE_FACTOR)),
-                    texture: texture.unwrap(),
+                    transform: Transform::from_translation(vec3(x, y, 0.0)).with_scale(Vec3::splat(MAP_SCALE_FACTOR)),
+                    texture: texture_handler.details_handler.clone().unwrap(),
                     ..default()
                 },
                  TextureAtlas {
-                     layout: texture_atlas_layout.clone(),
-                     index: bioma.map_texture,
-                 }, AnimationTimer(Timer::from_seconds(ANIMATION_INTERVAL, TimerMode::Repeating)))
+                     layout: details_layout.clone(),
+                     index: detail,
+                 }, VegetationSprite(grid_x, grid_y), TerrainSprite)
             );
         }
     }
-    for detail in vege.iter() {
-        let (x, y) = detail.pos;
-        let (x, y) = grid_to_world(x as f32, y as f32);
-
-        let layout = TextureAtlasLayout::from_grid(UVec2 { x: TILE_WIDTH, y: TILE_HEIGHT }, 4, 4, None, None);
-        let texture_atlas_layout = texture_atlas_layout.add(layout);
+}
 
-        let texture = texture_handler.details_handler.clone();
-        commands.spawn(
-            (SpriteBundle {
-                transform: Transform::from_translation(vec3(x, y, detail.z_index as f32)).with_scale(Vec3::splat(MAP_SCALE_FACTOR)),
-                texture: texture.unwrap(),
-                ..default()
-            },
-             TextureAtlas {
-                 layout: texture_atlas_layout.clone(),
-                 index: detail.map_texture,
-             }, AnimationTimer(Timer::from_seconds(ANIMATION_INTERVAL, TimerMode::Repeating)))
-        );
+fn despawn_removed_vegetation(
+    mut commands: Commands,
+    mut events: EventReader<VegetationRemoved>,
+    query: Query<(Entity, &VegetationSprite)>,
+) {
+    for event in events.read() {
+        for (entity, sprite) in &query {
+            if (sprite.0, sprite.1) == (event.x, event.y) {
+                commands.entity(entity).despawn();
+            }
+        }
     }
 }
 
@@ -201,6 +580,48 @@ fn grid_to_world(x:f32,y:f32)->(f32,f32){
     )
 }
 
+pub fn world_to_grid(pos: Vec2) -> (i32,i32){
+    ((pos.x / (TILE_WIDTH as f32 * MAP_SCALE_FACTOR)).round() as i32,
+     (pos.y / (TILE_HEIGHT as f32 * MAP_SCALE_FACTOR)).round() as i32
+    )
+}
+
+pub fn tile_world_size() -> Vec2 {
+    Vec2::new(TILE_WIDTH as f32 * MAP_SCALE_FACTOR, TILE_HEIGHT as f32 * MAP_SCALE_FACTOR)
+}
+
+/// Whether `position` is inside the player's current vision, as opposed to
+/// merely explored. See `WeatherState::vision_radius`.
+pub fn in_vision(player: Vec2, position: Vec2, radius: i32) -> bool {
+    let (px, py) = world_to_grid(player);
+    let (x, y) = world_to_grid(position);
+    let (dx, dy) = (x - px, y - py);
+    dx * dx + dy * dy <= radius * radius
+}
+
+/// Player and enemies (everything simulated) are tinted in water, lighter when
+/// wading and darker when swimming, on top of their own `BaseTint`.
+fn tint_sprites_in_water(
+    terrain: Res<TerrainMap>,
+    mut query: Query<(&Transform, &mut Sprite, Option<&BaseTint>), With<Interpolated>>,
+) {
+    for (transform, mut sprite, base) in &mut query {
+        let movement = terrain.movement_at(transform.translation.truncate());
+        let water = if movement.swimming {
+            SWIMMING_TINT
+        } else if movement.wading {
+            WADING_TINT
+        } else {
+            Color::WHITE
+        };
+        let (base, water) = (base.map_or(Color::WHITE, |tint| tint.0).to_srgba(), water.to_srgba());
+        let color = Color::srgba(base.red * water.red, base.green * water.green, base.blue * water.blue, base.alpha * water.alpha);
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
+
 fn animate_sprite(
     time: Res<Time>,
     mut query: Query<(&mut AnimationTimer, &mut TextureAtlas, &mut WaterType),With<WaterType>>,
@@ -0,0 +1,100 @@
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use crate::config::TICK_RATE;
+use crate::GameStatus;
+
+/// Runs gameplay in `FixedUpdate` at `tick_rate` Hz so outcomes don't depend on frame rate.
+/// Gameplay only ticks while `GameStatus::Started`.
+pub struct TimestepPlugin {
+    pub tick_rate: f64,
+}
+
+/// Ticks per second the simulation was configured with.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct TickRate(pub f64);
+
+/// Order of the gameplay systems inside a tick. Explicit so runs are reproducible.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SimSet {
+    Input,
+    /// Clock and other world state the player and enemies react to.
+    World,
+    Player,
+    /// What the player does to the world once moved, such as harvesting.
+    Action,
+    Enemy,
+}
+
+/// Draws an entity between its last two simulated positions instead of snapping
+/// to the latest tick. The simulation keeps writing `Transform` as usual.
+#[derive(Component)]
+pub struct Interpolated {
+    previous: Vec3,
+    current: Vec3,
+}
+
+pub struct InterpolationPlugin;
+
+/// Runs after render positions are interpolated, for anything that follows them (camera).
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InterpolationSet;
+
+impl Default for TimestepPlugin {
+    fn default() -> Self {
+        Self { tick_rate: TICK_RATE }
+    }
+}
+
+impl Plugin for TimestepPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Time::<Fixed>::from_hz(self.tick_rate))
+            .insert_resource(TickRate(self.tick_rate))
+            .init_state::<GameStatus>()
+            .configure_sets(FixedUpdate, (SimSet::Input, SimSet::World, SimSet::Player, SimSet::Action, SimSet::Enemy)
+                .chain()
+                .run_if(in_state(GameStatus::Started)));
+    }
+}
+
+impl Plugin for InterpolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.configure_sets(PostUpdate, InterpolationSet.before(TransformSystem::TransformPropagate))
+            .add_systems(FixedFirst, restore_simulated_translation)
+            .add_systems(FixedLast, record_simulated_translation)
+            .add_systems(PostUpdate, interpolate_translation.in_set(InterpolationSet));
+    }
+}
+
+impl Interpolated {
+    pub fn new(translation: Vec3) -> Self {
+        Self { previous: translation, current: translation }
+    }
+    /// Moves the entity without interpolating from where it was.
+    pub fn teleport(&mut self, translation: Vec3) {
+        self.previous = translation;
+        self.current = translation;
+    }
+}
+
+fn restore_simulated_translation(mut query: Query<(&mut Transform, &Interpolated)>) {
+    for (mut transform, interpolated) in &mut query {
+        transform.translation = interpolated.current;
+    }
+}
+
+fn record_simulated_translation(mut query: Query<(&Transform, &mut Interpolated)>) {
+    for (transform, mut interpolated) in &mut query {
+        interpolated.previous = interpolated.current;
+        interpolated.current = transform.translation;
+    }
+}
+
+fn interpolate_translation(
+    fixed_time: Res<Time<Fixed>>,
+    mut query: Query<(&mut Transform, &Interpolated)>,
+) {
+    let alpha = fixed_time.overstep_fraction();
+    for (mut transform, interpolated) in &mut query {
+        transform.translation = interpolated.previous.lerp(interpolated.current, alpha);
+    }
+}
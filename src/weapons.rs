@@ -0,0 +1,334 @@
+use std::f32::consts::TAU;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::assets_loader::load_data;
+use crate::combat::{DamageDealt, Team};
+use crate::config::{MAX_WEAPONS, WEAPON_RANGE};
+use crate::player::{Player, PlayerStats};
+use crate::projectile::{spawn_projectiles, FireProjectile, PROJECTILE_Z};
+use crate::spatial::EnemyGrid;
+use crate::timestep::SimSet;
+
+const BLADE_COLOR: Color = Color::srgb(0.8, 0.8, 0.85);
+/// Spread between projectiles fired together, in radians.
+const PROJECTILE_SPREAD: f32 = 0.15;
+const SLAM_EFFECT_TIME: f32 = 0.3;
+const SLAM_COLOR: Color = Color::srgb(0.9, 0.6, 0.2);
+
+/// Weapons the player carries, firing on their own cooldowns. Definitions come
+/// from `assets/weapons.ron`; new weapons and levels are taken as upgrades.
+#[derive(Default)]
+pub struct WeaponsPlugin {
+    pub headless: bool,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WeaponKind {
+    /// Blades circling the player, hitting whatever they pass through, each
+    /// enemy once per cooldown.
+    Orbit,
+    /// Thrown at the nearest enemy.
+    Projectile,
+    /// Hits everything around the player at once.
+    Slam,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct WeaponLevel {
+    pub damage: i32,
+    pub area: f32,
+    pub cooldown: f32,
+    #[serde(default = "one")]
+    pub count: u32,
+    #[serde(default)]
+    pub speed: f32,
+    /// Extra enemies a projectile passes through.
+    #[serde(default)]
+    pub pierce: u32,
+    /// Hit radius of each orbiting blade.
+    #[serde(default)]
+    pub blade_radius: f32,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct WeaponDef {
+    pub id: String,
+    pub name: String,
+    pub kind: WeaponKind,
+    pub levels: Vec<WeaponLevel>,
+}
+
+#[derive(Resource)]
+pub struct WeaponDatabase {
+    weapons: Vec<WeaponDef>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EquippedWeapon {
+    pub id: String,
+    /// Starts at 1.
+    pub level: u32,
+    cooldown: f32,
+    /// Where orbiting blades are on their circle.
+    angle: f32,
+    /// Enemies orbiting blades hit lately, and how long until they can be hit again.
+    #[serde(skip)]
+    hits: Vec<(Entity, f32)>,
+}
+
+/// Stored in saves.
+#[derive(Component, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Weapons {
+    pub equipped: Vec<EquippedWeapon>,
+}
+
+/// Sent when a slam lands, for its effect.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SlamLanded {
+    pub position: Vec2,
+    pub radius: f32,
+}
+
+#[derive(Component)]
+struct OrbitBlade;
+#[derive(Component)]
+struct SlamEffect {
+    radius: f32,
+    timer: Timer,
+}
+
+impl Plugin for WeaponsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(WeaponDatabase::load())
+            .add_event::<SlamLanded>()
+            .add_systems(FixedUpdate, fire_weapons.in_set(SimSet::Action).before(spawn_projectiles));
+        if !self.headless {
+            app.add_systems(Update, (
+                update_orbit_blades,
+                spawn_slam_effects,
+                draw_slam_effects,
+            ));
+        }
+    }
+}
+
+impl WeaponsPlugin {
+    /// Weapon logic only: no sprites or effects.
+    pub fn headless() -> Self {
+        Self { headless: true }
+    }
+}
+
+fn one() -> u32 {
+    1
+}
+
+impl WeaponDatabase {
+    /// Reads the weapon definitions from `assets/weapons.ron`.
+    pub fn load() -> Self {
+        let weapons: Vec<WeaponDef> = load_data("weapons.ron");
+        Self { weapons }
+    }
+    pub fn get(&self, id: &str) -> Option<&WeaponDef> {
+        self.weapons.iter().find(|weapon| weapon.id == id)
+    }
+}
+
+impl WeaponDef {
+    pub fn level(&self, level: u32) -> Option<&WeaponLevel> {
+        self.levels.get(level.checked_sub(1)? as usize)
+    }
+    pub fn max_level(&self) -> u32 {
+        self.levels.len() as u32
+    }
+}
+
+impl EquippedWeapon {
+    pub fn new(id: &str) -> Self {
+        Self { id: id.to_string(), level: 1, cooldown: 0.0, angle: 0.0, hits: Vec::new() }
+    }
+}
+
+impl Default for Weapons {
+    /// Every run starts with a throwing axe.
+    fn default() -> Self {
+        Self { equipped: vec![EquippedWeapon::new("throwing_axe")] }
+    }
+}
+
+impl Weapons {
+    pub fn level(&self, id: &str) -> Option<u32> {
+        self.equipped.iter().find(|weapon| weapon.id == id).map(|weapon| weapon.level)
+    }
+    /// Whether `upgrade` would change anything: the weapon is below its max
+    /// level, or there is a free slot for it.
+    pub fn can_upgrade(&self, weapons: &WeaponDatabase, id: &str) -> bool {
+        let Some(def) = weapons.get(id) else { return false; };
+        match self.level(id) {
+            Some(level) => level < def.max_level(),
+            None => self.equipped.len() < MAX_WEAPONS,
+        }
+    }
+    /// Levels up the weapon, or equips it at level 1.
+    pub fn upgrade(&mut self, weapons: &WeaponDatabase, id: &str) {
+        if !self.can_upgrade(weapons, id) {
+            return;
+        }
+        match self.equipped.iter_mut().find(|weapon| weapon.id == id) {
+            Some(weapon) => weapon.level += 1,
+            None => self.equipped.push(EquippedWeapon::new(id)),
+        }
+    }
+}
+
+fn fire_weapons(
+    time: Res<Time>,
+    weapons: Res<WeaponDatabase>,
+    grid: Res<EnemyGrid>,
+    (mut damage, mut shots, mut slams): (EventWriter<DamageDealt>, EventWriter<FireProjectile>, EventWriter<SlamLanded>),
+    mut player_query: Query<(&Transform, &PlayerStats, &mut Weapons), With<Player>>,
+) {
+    let Ok((transform, stats, mut equipped)) = player_query.get_single_mut() else { return; };
+    let position = transform.translation.truncate();
+    let delta = time.delta_seconds();
+
+    for weapon in equipped.equipped.iter_mut() {
+        let Some(def) = weapons.get(&weapon.id) else { continue; };
+        let Some(level) = def.level(weapon.level) else { continue; };
+        let amount = (level.damage as f32 * stats.attack_damage).round() as i32;
+        let area = level.area * stats.attack_area;
+
+        weapon.cooldown = (weapon.cooldown - delta).max(0.0);
+        match def.kind {
+            WeaponKind::Orbit => {
+                // Blades cut every tick, but leave each enemy alone for the cooldown after a hit
+                weapon.angle = (weapon.angle + level.speed * delta) % TAU;
+                weapon.hits.retain_mut(|(_, time_left)| {
+                    *time_left -= delta;
+                    *time_left > 0.0
+                });
+                for blade in 0..level.count {
+                    let center = position + orbit_offset(weapon.angle, blade, level.count, area);
+                    for target in grid.within(center, level.blade_radius * stats.attack_area) {
+                        if weapon.hits.iter().any(|(hit, _)| *hit == target) {
+                            continue;
+                        }
+                        weapon.hits.push((target, level.cooldown));
+                        damage.send(DamageDealt { target, amount, team: Team::Player });
+                    }
+                }
+            }
+            _ if weapon.cooldown > 0.0 => {}
+            WeaponKind::Projectile => {
+                // Holds its throw until something is in range
+                let Some((_, target)) = grid.nearest(position, WEAPON_RANGE) else { continue; };
+                let aim = (target - position).try_normalize().unwrap_or(Vec2::X);
+                let first = -(level.count as f32 - 1.0) / 2.0;
+                for index in 0..level.count {
+                    let direction = Vec2::from_angle((first + index as f32) * PROJECTILE_SPREAD).rotate(aim);
+                    shots.send(FireProjectile {
+                        team: Team::Player,
+                        position,
+                        velocity: direction * level.speed,
+                        damage: amount,
+                        radius: area,
+                        pierce: level.pierce,
+                    });
+                }
+                weapon.cooldown = level.cooldown;
+            }
+            WeaponKind::Slam => {
+                for target in grid.within(position, area) {
+                    damage.send(DamageDealt { target, amount, team: Team::Player });
+                }
+                slams.send(SlamLanded { position, radius: area });
+                weapon.cooldown = level.cooldown;
+            }
+        }
+    }
+}
+
+/// Where blade `index` of `count` sits on an orbit of `radius`.
+fn orbit_offset(angle: f32, index: u32, count: u32, radius: f32) -> Vec2 {
+    Vec2::from_angle(angle + TAU * index as f32 / count.max(1) as f32) * radius
+}
+
+/// Keeps one sprite per orbiting blade on the player's orbit weapons.
+fn update_orbit_blades(
+    mut commands: Commands,
+    weapons: Res<WeaponDatabase>,
+    player_query: Query<(&Transform, &PlayerStats, &Weapons), Without<OrbitBlade>>,
+    mut blade_query: Query<(Entity, &mut Transform, &mut Sprite), With<OrbitBlade>>,
+) {
+    let mut offsets = Vec::new();
+    if let Ok((transform, stats, equipped)) = player_query.get_single() {
+        let position = transform.translation.truncate();
+        for weapon in &equipped.equipped {
+            let Some(def) = weapons.get(&weapon.id).filter(|def| def.kind == WeaponKind::Orbit) else { continue; };
+            let Some(level) = def.level(weapon.level) else { continue; };
+            let size = Vec2::splat(level.blade_radius * stats.attack_area * 2.0);
+            for blade in 0..level.count {
+                let offset = orbit_offset(weapon.angle, blade, level.count, level.area * stats.attack_area);
+                offsets.push((position + offset, weapon.angle * 4.0, size));
+            }
+        }
+    }
+
+    let mut blades = blade_query.iter_mut();
+    for (position, spin, size) in offsets {
+        let transform = Transform::from_translation(position.extend(PROJECTILE_Z)).with_rotation(Quat::from_rotation_z(spin));
+        match blades.next() {
+            Some((_, mut blade, mut sprite)) => {
+                *blade = transform;
+                sprite.custom_size = Some(size);
+            }
+            None => {
+                commands.spawn((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: BLADE_COLOR,
+                            custom_size: Some(size),
+                            ..default()
+                        },
+                        transform,
+                        ..default()
+                    },
+                    OrbitBlade,
+                ));
+            }
+        }
+    }
+    for (entity, ..) in blades {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn spawn_slam_effects(mut commands: Commands, mut slams: EventReader<SlamLanded>) {
+    for slam in slams.read() {
+        commands.spawn((
+            SpatialBundle::from_transform(Transform::from_translation(slam.position.extend(PROJECTILE_Z))),
+            SlamEffect { radius: slam.radius, timer: Timer::from_seconds(SLAM_EFFECT_TIME, TimerMode::Once) },
+        ));
+    }
+}
+
+/// An expanding, fading ring.
+fn draw_slam_effects(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut gizmos: Gizmos,
+    mut query: Query<(Entity, &Transform, &mut SlamEffect)>,
+) {
+    for (entity, transform, mut effect) in &mut query {
+        if effect.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let progress = effect.timer.fraction();
+        gizmos.circle_2d(
+            transform.translation.truncate(),
+            effect.radius * (0.5 + progress / 2.0),
+            SLAM_COLOR.with_alpha(1.0 - progress),
+        );
+    }
+}
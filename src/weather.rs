@@ -0,0 +1,260 @@
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use crate::config::{VISION_RADIUS, WEATHER_DURATION, WEATHER_TRANSITION};
+use crate::logging::WEATHER_TARGET;
+use crate::rng::WorldSeed;
+use crate::terrain::Biome;
+use crate::timestep::SimSet;
+
+const MAX_PARTICLES: usize = 300;
+/// Above the player and enemies.
+const PARTICLE_Z: f32 = 5.0;
+const PARTICLE_SIZE: Vec2 = Vec2::new(2.0, 14.0);
+const PARTICLE_COLOR: Color = Color::srgba(0.65, 0.75, 0.95, 0.55);
+
+/// Weather that changes every `WEATHER_DURATION` seconds, fading from one
+/// state to the next. The sequence only depends on the world seed.
+#[derive(Default)]
+pub struct WeatherPlugin {
+    pub headless: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Serialize, Deserialize)]
+pub enum Weather {
+    #[default]
+    Clear,
+    Rain,
+    Fog,
+    Storm,
+}
+
+/// Where the run is in its weather sequence. Stored in saves.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WeatherState {
+    seed: u64,
+    elapsed: f32,
+    pub current: Weather,
+    pub previous: Weather,
+}
+
+#[derive(Component)]
+struct WeatherParticle;
+#[derive(Component)]
+struct WeatherTint;
+
+impl Plugin for WeatherPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WeatherState>()
+            .add_systems(FixedUpdate, advance_weather.in_set(SimSet::World));
+        if !self.headless {
+            app.add_systems(PostStartup, weather_spawn)
+                .add_systems(Update, (update_weather_tint, update_particles));
+        }
+    }
+}
+
+impl WeatherPlugin {
+    /// Weather state and its gameplay effects only: no particles or tint.
+    pub fn headless() -> Self {
+        Self { headless: true }
+    }
+}
+
+impl FromWorld for WeatherState {
+    fn from_world(world: &mut World) -> Self {
+        Self::new(*world.resource::<WorldSeed>())
+    }
+}
+
+impl Weather {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Weather::Clear => "Clear",
+            Weather::Rain => "Rain",
+            Weather::Fog => "Fog",
+            Weather::Storm => "Storm",
+        }
+    }
+    /// Weather for the `period`th stretch of the run.
+    fn roll(seed: u64, period: u64) -> Self {
+        // Own generator per period so the sequence does not depend on gameplay draws
+        let mut rng = StdRng::seed_from_u64(seed ^ period.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        match rng.gen_range(0..20) {
+            0..=9 => Weather::Clear,
+            10..=13 => Weather::Rain,
+            14..=16 => Weather::Fog,
+            _ => Weather::Storm,
+        }
+    }
+    fn tint(&self) -> Color {
+        match self {
+            Weather::Clear => Color::NONE,
+            Weather::Rain => Color::srgba(0.2, 0.25, 0.35, 0.2),
+            Weather::Fog => Color::srgba(0.7, 0.72, 0.75, 0.4),
+            Weather::Storm => Color::srgba(0.08, 0.08, 0.15, 0.35),
+        }
+    }
+    /// Particle count and fall velocity.
+    fn particles(&self) -> (usize, Vec2) {
+        match self {
+            Weather::Rain => (MAX_PARTICLES / 2, Vec2::new(-60.0, -900.0)),
+            Weather::Storm => (MAX_PARTICLES, Vec2::new(-350.0, -1200.0)),
+            Weather::Clear | Weather::Fog => (0, Vec2::ZERO),
+        }
+    }
+}
+
+impl WeatherState {
+    pub fn new(seed: WorldSeed) -> Self {
+        Self::restore(seed, 0.0)
+    }
+    /// State after `elapsed` seconds of the run.
+    pub fn restore(seed: WorldSeed, elapsed: f32) -> Self {
+        let mut state = Self {
+            seed: seed.0,
+            elapsed: 0.0,
+            current: Weather::Clear,
+            previous: Weather::Clear,
+        };
+        state.advance(elapsed);
+        state
+    }
+    pub fn advance(&mut self, seconds: f32) {
+        self.elapsed += seconds;
+        let period = (self.elapsed / WEATHER_DURATION) as u64;
+        // Every run starts clear
+        self.current = if period == 0 { Weather::Clear } else { Weather::roll(self.seed, period) };
+        self.previous = if period <= 1 { Weather::Clear } else { Weather::roll(self.seed, period - 1) };
+    }
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+    /// How far the change from `previous` to `current` has gone, 0 to 1.
+    pub fn transition(&self) -> f32 {
+        (self.elapsed % WEATHER_DURATION / WEATHER_TRANSITION).min(1.0)
+    }
+
+    pub fn speed_multiplier(&self, biome: Option<Biome>) -> f32 {
+        match (self.current, biome) {
+            (Weather::Rain | Weather::Storm, Some(Biome::Grass)) => 0.8,
+            _ => 1.0,
+        }
+    }
+    pub fn detection_multiplier(&self) -> f32 {
+        match self.current {
+            Weather::Fog => 0.6,
+            _ => 1.0,
+        }
+    }
+    /// Tiles around the player that are visible and get explored.
+    pub fn vision_radius(&self) -> i32 {
+        match self.current {
+            Weather::Fog => VISION_RADIUS * 5 / 8,
+            _ => VISION_RADIUS,
+        }
+    }
+    pub fn spawn_multiplier(&self) -> f32 {
+        match self.current {
+            Weather::Storm => 1.5,
+            _ => 1.0,
+        }
+    }
+}
+
+fn advance_weather(time: Res<Time>, mut weather: ResMut<WeatherState>) {
+    let before = weather.current;
+    weather.advance(time.delta_seconds());
+    if weather.current != before {
+        info!(target: WEATHER_TARGET, "Weather changed to {}", weather.current.label());
+    }
+}
+
+fn weather_spawn(mut commands: Commands) {
+    // Over the world, behind the night tint and the rest of the UI
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            z_index: ZIndex::Global(-11),
+            ..default()
+        },
+        WeatherTint,
+    ));
+
+    for _ in 0..MAX_PARTICLES {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: PARTICLE_COLOR,
+                    custom_size: Some(PARTICLE_SIZE),
+                    ..default()
+                },
+                transform: Transform::from_xyz(0.0, 0.0, PARTICLE_Z),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            WeatherParticle,
+        ));
+    }
+}
+
+fn update_weather_tint(weather: Res<WeatherState>, mut query: Query<&mut BackgroundColor, With<WeatherTint>>) {
+    let (from, to) = (weather.previous.tint(), weather.current.tint());
+    let blend = weather.transition();
+    // Clear has no colour of its own, so fading to or from it only fades the alpha
+    let color = match (from.alpha() == 0.0, to.alpha() == 0.0) {
+        (true, _) => to,
+        (_, true) => from,
+        _ => from.mix(&to, blend),
+    };
+    let tint = color.with_alpha(from.alpha() * (1.0 - blend) + to.alpha() * blend);
+    for mut color in &mut query {
+        color.0 = tint;
+    }
+}
+
+/// Falling drops, wrapped around the camera view so a fixed pool covers any screen.
+fn update_particles(
+    time: Res<Time>,
+    weather: Res<WeatherState>,
+    camera_query: Query<(&Transform, &OrthographicProjection), Without<WeatherParticle>>,
+    mut particle_query: Query<(&mut Transform, &mut Visibility), With<WeatherParticle>>,
+) {
+    let Ok((camera, projection)) = camera_query.get_single() else { return; };
+    let (previous_count, previous_velocity) = weather.previous.particles();
+    let (count, velocity) = weather.current.particles();
+    let blend = weather.transition();
+    let active = (previous_count as f32 * (1.0 - blend) + count as f32 * blend) as usize;
+    // Drops fading out keep falling the way they were
+    let velocity = if count > 0 { velocity } else { previous_velocity };
+
+    let center = camera.translation.truncate();
+    let half = projection.area.half_size();
+    let angle = velocity.x.atan2(-velocity.y);
+    for (index, (mut transform, mut visibility)) in particle_query.iter_mut().enumerate() {
+        if index >= active {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        }
+        if *visibility == Visibility::Hidden {
+            // Spread new drops over the view instead of starting them in a line
+            let spread = (index as f32 * 0.618_034).fract();
+            let offset = Vec2::new(spread * 2.0 - 1.0, ((index * 37) % 100) as f32 / 50.0 - 1.0) * half;
+            transform.translation = (center + offset).extend(PARTICLE_Z);
+            *visibility = Visibility::Inherited;
+        }
+
+        let mut position = transform.translation.truncate() + velocity * time.delta_seconds() - center;
+        position.x = (position.x + half.x).rem_euclid(half.x * 2.0) - half.x;
+        position.y = (position.y + half.y).rem_euclid(half.y * 2.0) - half.y;
+        transform.translation = (center + position).extend(PARTICLE_Z);
+        transform.rotation = Quat::from_rotation_z(angle);
+    }
+}
@@ -0,0 +1,583 @@
+use std::time::Duration;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use orc_wars::assets_loader::load_data;
+use orc_wars::boss::{Boss, BossAttack, BossDefeated};
+use orc_wars::config::{ENEMY_CHASE_RANGE, HARVEST_COOLDOWN, LOOT_LIFETIME, MAP_COLUMNS, MAP_ROWS, MAX_ENEMIES, PLAYER_MAX_STAMINA, SPAWN_INTERVAL, TICK_RATE, UPGRADE_CHOICES, VISION_RADIUS, WEATHER_DURATION};
+use orc_wars::combat::Team;
+use orc_wars::daynight::GameClock;
+use orc_wars::enemy::{enemy_bundle, Enemy, EnemyDatabase, EnemyState, EnemyType, WaveState};
+use orc_wars::inventory::{Inventory, ItemDatabase, Pickup, PickupLifetime};
+use orc_wars::leveling::{Experience, UpgradeChoices};
+use orc_wars::loot::LootTables;
+use orc_wars::player::{Player, PlayerInput, PlayerStats};
+use orc_wars::projectile::{FireProjectile, Projectile, ProjectilePool};
+use orc_wars::replay::{Replay, ReplayMode, ReplayPlugin, ReplayResult};
+use orc_wars::rng::WorldSeed;
+use orc_wars::save::{LoadGameEvent, SaveDir, SaveGame, SaveGameEvent, SavePlugin, SaveSlot, SAVE_VERSION};
+use orc_wars::terrain::{tile_world_size, world_to_grid, Biome, TerrainMap};
+use orc_wars::weapons::{EquippedWeapon, Weapons};
+use orc_wars::weather::WeatherState;
+use orc_wars::{GameStatus, SimulationPlugins};
+
+fn frame() -> Duration {
+    // One fixed tick per update
+    Duration::from_secs_f64(1.0 / TICK_RATE)
+}
+
+fn headless_app() -> App {
+    seeded_app(WorldSeed(1))
+}
+
+fn seeded_app(seed: WorldSeed) -> App {
+    let mut app = build_app(seed);
+    app.update();
+    app
+}
+
+fn build_app(seed: WorldSeed) -> App {
+    let mut app = App::new();
+    app.insert_resource(seed)
+        .add_plugins(MinimalPlugins)
+        .add_plugins(SimulationPlugins)
+        .insert_resource(TimeUpdateStrategy::ManualDuration(frame()));
+    app
+}
+
+fn step(app: &mut App, frames: usize) {
+    for _ in 0..frames {
+        app.update();
+    }
+}
+
+fn player_position(app: &mut App) -> Vec3 {
+    app.world_mut()
+        .query_filtered::<&Transform, With<Player>>()
+        .single(app.world())
+        .translation
+}
+
+fn enemy_count(app: &mut App) -> usize {
+    app.world_mut()
+        .query_filtered::<Entity, With<Enemy>>()
+        .iter(app.world())
+        .len()
+}
+
+#[test]
+fn terrain_is_generated_without_sprites() {
+    let mut app = headless_app();
+
+    let terrain = app.world().resource::<TerrainMap>();
+    assert_eq!((terrain.rows, terrain.columns), (MAP_ROWS, MAP_COLUMNS));
+    assert!(terrain.get(0, 0).is_some());
+    assert!(terrain.get(MAP_ROWS as i32, 0).is_none());
+
+    let sprites = app.world_mut().query::<&Sprite>().iter(app.world()).len();
+    assert_eq!(sprites, 0);
+}
+
+#[test]
+fn player_moves_with_scripted_input() {
+    let mut app = headless_app();
+    let start = player_position(&mut app);
+
+    app.insert_resource(PlayerInput { right: true, up: true, ..default() });
+    step(&mut app, 30);
+    let walked = player_position(&mut app);
+    assert!(walked.x > start.x);
+    assert!(walked.y > start.y);
+
+    app.insert_resource(PlayerInput::default());
+    step(&mut app, 10);
+    assert_eq!(player_position(&mut app), walked);
+}
+
+#[test]
+fn sprinting_drains_stamina() {
+    let mut app = headless_app();
+
+    app.insert_resource(PlayerInput { left: true, sprint: true, ..default() });
+    step(&mut app, 60);
+
+    let player = app.world_mut().query::<&Player>().single(app.world());
+    assert!(player.stamina < PLAYER_MAX_STAMINA);
+    assert_eq!(player.health, 100);
+}
+
+#[test]
+fn swimming_drains_stamina() {
+    let mut app = headless_app();
+    let terrain = app.world().resource::<TerrainMap>();
+    let ((x, y), _) = terrain.tiles()
+        .find(|(_, tile)| tile.biome == Biome::DeepWater)
+        .expect("seed has deep water");
+    let position = (Vec2::new(x as f32, y as f32) * tile_world_size()).extend(1.0);
+    app.world_mut().query_filtered::<&mut Transform, With<Player>>()
+        .single_mut(app.world_mut())
+        .translation = position;
+
+    step(&mut app, 60);
+    let player = app.world_mut().query::<&Player>().single(app.world());
+    assert!(player.stamina < PLAYER_MAX_STAMINA);
+}
+
+#[test]
+fn chopping_a_tree_yields_wood() {
+    let mut app = headless_app();
+    let (x, y) = world_to_grid(player_position(&mut app).truncate());
+    let mut terrain = app.world_mut().resource_mut::<TerrainMap>();
+    for (dx, dy) in [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)] {
+        terrain.set_vegetation(x + dx, y + dy, None);
+    }
+    // Atlas index 3 is a tree
+    terrain.set_vegetation(x, y, Some(3));
+
+    app.insert_resource(PlayerInput { interact: true, ..default() });
+    step(&mut app, (HARVEST_COOLDOWN as f64 * TICK_RATE) as usize * 4 + 2);
+    assert_eq!(app.world().resource::<TerrainMap>().tile(x, y).unwrap().vegetation, None);
+
+    // The wood drops as a pickup, pulled in by the magnet
+    step(&mut app, 10);
+    let inventory = app.world_mut().query::<&Inventory>().single(app.world());
+    assert_eq!(inventory.count("wood"), 3);
+}
+
+#[test]
+fn health_potion_heals_from_the_hotbar() {
+    let mut app = headless_app();
+    let items = ItemDatabase::load();
+    let (mut player, mut inventory) = app.world_mut().query::<(&mut Player, &mut Inventory)>().single_mut(app.world_mut());
+    player.health = 30;
+    assert_eq!(inventory.add(&items, "health_potion", 2), 0);
+
+    app.insert_resource(PlayerInput { use_slot: Some(0), ..default() });
+    step(&mut app, 1);
+    let (player, inventory) = app.world_mut().query::<(&Player, &Inventory)>().single(app.world());
+    assert_eq!(player.health, 70);
+    assert_eq!(inventory.count("health_potion"), 1);
+
+    // At full health the potion is kept
+    let (mut player, stats) = app.world_mut().query::<(&mut Player, &PlayerStats)>().single_mut(app.world_mut());
+    player.health = stats.max_health;
+    app.insert_resource(PlayerInput { use_slot: Some(0), ..default() });
+    step(&mut app, 1);
+    assert_eq!(app.world_mut().query::<&Inventory>().single(app.world()).count("health_potion"), 1);
+
+    // Dragging it elsewhere is input too, applied by the next tick
+    app.insert_resource(PlayerInput { move_slot: Some((0, 3)), ..default() });
+    step(&mut app, 1);
+    let inventory = app.world_mut().query::<&Inventory>().single(app.world());
+    assert!(inventory.get(0).is_none());
+    assert_eq!(inventory.get(3).map(|stack| stack.item.as_str()), Some("health_potion"));
+}
+
+#[test]
+fn enemies_spawn_on_interval() {
+    let mut app = headless_app();
+    assert_eq!(enemy_count(&mut app), 0);
+
+    let frames = (SPAWN_INTERVAL as f64 * TICK_RATE).ceil() as usize + 2;
+    step(&mut app, frames);
+    assert!(enemy_count(&mut app) > 0);
+}
+
+#[test]
+fn mini_boss_drops_loot_that_expires() {
+    let mut app = headless_app();
+    // Out of the magnet's reach, so the loot stays on the ground
+    let position = player_position(&mut app).truncate() + Vec2::new(1000.0, 0.0);
+    app.world_mut().spawn(enemy_bundle(EnemyType::new("mini_boss"), EnemyState::default(), position, 0));
+
+    step(&mut app, 1);
+    assert_eq!(app.world().resource::<WaveState>().kills, 1);
+    let mut pickups = app.world_mut().query_filtered::<&Pickup, With<PickupLifetime>>();
+    assert!(pickups.iter(app.world()).any(|pickup| pickup.item == "health_potion"));
+
+    // The kill levels the player up, which pauses until an upgrade is picked
+    app.insert_resource(PlayerInput { pick_upgrade: Some(0), ..default() });
+    app.world_mut().resource_mut::<NextState<GameStatus>>().set(GameStatus::Started);
+    step(&mut app, (LOOT_LIFETIME as f64 * TICK_RATE) as usize + 1);
+    assert_eq!(pickups.iter(app.world()).len(), 0);
+    // Boss rewards stay until picked up
+    let mut rewards = app.world_mut().query_filtered::<&Pickup, Without<PickupLifetime>>();
+    assert!(rewards.iter(app.world()).len() > 0);
+}
+
+#[test]
+fn mini_boss_fights_in_phases_and_rewards_its_defeat() {
+    let mut app = headless_app();
+    app.world_mut().query::<&mut Weapons>().single_mut(app.world_mut()).equipped.clear();
+    let position = player_position(&mut app).truncate() + Vec2::new(500.0, 0.0);
+    let boss = app.world_mut().spawn(enemy_bundle(EnemyType::new("mini_boss"), EnemyState::default(), position, 120)).id();
+
+    step(&mut app, 2);
+    assert!(app.world().get::<Boss>(boss).unwrap().engaged);
+    assert_eq!(app.world().get::<Boss>(boss).unwrap().phase, 0);
+
+    // Its first special attack is wound up before it lands
+    step(&mut app, (4.0 * TICK_RATE) as usize + 2);
+    assert!(app.world().get::<Boss>(boss).unwrap().windup.is_some());
+
+    app.world_mut().get_mut::<Enemy>(boss).unwrap().health = 30;
+    step(&mut app, 1);
+    assert_eq!(app.world().get::<Boss>(boss).unwrap().phase, 2);
+
+    app.world_mut().get_mut::<Enemy>(boss).unwrap().health = 0;
+    step(&mut app, 1);
+    let defeated = app.world().resource::<Events<BossDefeated>>();
+    assert_eq!(defeated.get_reader().read(defeated).map(|event| event.name.as_str()).collect::<Vec<_>>(), ["Orc Warlord"]);
+    let mut rewards = app.world_mut().query_filtered::<&Pickup, Without<PickupLifetime>>();
+    assert!(rewards.iter(app.world()).any(|pickup| pickup.item == "stamina_tonic"));
+}
+
+#[test]
+fn enemy_registry_refers_to_known_loot() {
+    let enemies = EnemyDatabase::load();
+    let tables = LootTables::load();
+    assert!(enemies.iter().any(|def| def.spawn_weight > 0));
+    for def in enemies.iter() {
+        if let Some(loot) = &def.loot {
+            assert!(tables.contains_key(loot), "{} drops from unknown table {}", def.id, loot);
+        }
+        for row in [Some(def.sprite.idle), Some(def.sprite.run), def.sprite.attack].into_iter().flatten() {
+            assert!(row + 4 <= def.sprite.rows, "{} animates past its sheet", def.id);
+        }
+        let attacks = def.boss.iter().flat_map(|boss| &boss.phases).flat_map(|phase| &phase.attacks);
+        for attack in attacks {
+            if let BossAttack::Summon { enemy, .. } = attack {
+                assert!(enemies.get(&EnemyType::new(enemy)).is_some(), "{} summons unknown enemy {}", def.id, enemy);
+            }
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "missing.ron could not be read")]
+fn missing_data_files_stop_the_game() {
+    load_data::<Vec<String>>("missing.ron");
+}
+
+#[test]
+fn kills_level_up_into_an_upgrade_pick() {
+    let mut app = headless_app();
+    let position = player_position(&mut app).truncate() + Vec2::new(1000.0, 0.0);
+    let needed = Experience::xp_to_next(1).div_ceil(EnemyDatabase::load().get(&EnemyType::new("slave")).unwrap().xp);
+    for _ in 0..needed {
+        app.world_mut().spawn(enemy_bundle(EnemyType::new("slave"), EnemyState::default(), position, 0));
+    }
+
+    step(&mut app, 2);
+    assert_eq!(*app.world().resource::<State<GameStatus>>().get(), GameStatus::LevelUp);
+    assert_eq!(app.world().resource::<UpgradeChoices>().options.len(), UPGRADE_CHOICES);
+    let experience = *app.world_mut().query::<&Experience>().single(app.world());
+    assert_eq!(experience.level, 2);
+
+    // The pick is input for the first tick after resuming
+    let before = app.world_mut().query::<&PlayerStats>().single(app.world()).clone();
+    app.insert_resource(PlayerInput { pick_upgrade: Some(0), ..default() });
+    app.world_mut().resource_mut::<NextState<GameStatus>>().set(GameStatus::Started);
+    step(&mut app, 1);
+    assert!(app.world().resource::<UpgradeChoices>().options.is_empty());
+    assert_ne!(*app.world_mut().query::<&PlayerStats>().single(app.world()), before);
+}
+
+#[test]
+fn throwing_axe_hits_the_nearest_enemy() {
+    let mut app = headless_app();
+    let weapons = app.world_mut().query::<&Weapons>().single(app.world()).clone();
+    assert_eq!(weapons.level("throwing_axe"), Some(1));
+
+    let position = player_position(&mut app).truncate() + Vec2::new(300.0, 0.0);
+    let enemy = app.world_mut().spawn(enemy_bundle(EnemyType::new("slave"), EnemyState::default(), position, 100)).id();
+    step(&mut app, 2);
+    let mut projectiles = app.world_mut().query::<&Projectile>();
+    assert_eq!(projectiles.iter(app.world()).filter(|projectile| projectile.is_active()).count(), 1);
+
+    step(&mut app, 30);
+    assert!(app.world().get::<Enemy>(enemy).unwrap().health < 100);
+    assert_eq!(projectiles.iter(app.world()).filter(|projectile| projectile.is_active()).count(), 0);
+    assert_eq!(app.world().resource::<ProjectilePool>().len(), 1);
+
+    // The next throw reuses the parked axe instead of spawning another
+    step(&mut app, 60);
+    assert_eq!(projectiles.iter(app.world()).len(), 1);
+}
+
+#[test]
+fn boss_summons_stop_at_the_enemy_cap() {
+    let mut app = headless_app();
+    app.world_mut().query::<&mut Weapons>().single_mut(app.world_mut()).equipped.clear();
+    let player = player_position(&mut app).truncate();
+    // Far out of reach, leaving room for one of the three summoned slaves
+    while enemy_count(&mut app) < MAX_ENEMIES - 2 {
+        app.world_mut().spawn(enemy_bundle(EnemyType::new("slave"), EnemyState::default(), player + Vec2::new(0.0, 5000.0), 30));
+    }
+    // Low enough to open with a summon
+    app.world_mut().spawn(enemy_bundle(EnemyType::new("mini_boss"), EnemyState::default(), player + Vec2::new(300.0, 0.0), 30));
+
+    step(&mut app, (6.0 * TICK_RATE) as usize);
+    assert_eq!(enemy_count(&mut app), MAX_ENEMIES);
+}
+
+#[test]
+fn spinning_axes_hit_each_enemy_once_per_cooldown() {
+    let mut app = headless_app();
+    app.world_mut().query::<&mut Weapons>().single_mut(app.world_mut()).equipped = vec![EquippedWeapon::new("spinning_axes")];
+    // Held on the blade's circle, rather than chasing into the player
+    let position = player_position(&mut app) + Vec3::new(110.0, 0.0, 0.0);
+    let enemy = app.world_mut().spawn(enemy_bundle(EnemyType::new("slave"), EnemyState::default(), position.truncate(), 100)).id();
+    let hold = |app: &mut App, frames: usize| {
+        for _ in 0..frames {
+            app.world_mut().get_mut::<Transform>(enemy).unwrap().translation = position;
+            step(app, 1);
+        }
+        app.world().get::<Enemy>(enemy).unwrap().health
+    };
+
+    let health = hold(&mut app, 2);
+    assert!(health < 100);
+    assert_eq!(hold(&mut app, (0.25 * TICK_RATE) as usize), health);
+
+    // Once the cooldown is over the blade cuts it again on its next pass
+    assert!(hold(&mut app, (3.0 * TICK_RATE) as usize) < health);
+}
+
+#[test]
+fn enemy_projectiles_hit_the_player() {
+    let mut app = headless_app();
+    let player = player_position(&mut app).truncate();
+    app.world_mut().send_event(FireProjectile {
+        team: Team::Enemy,
+        position: player + Vec2::new(-200.0, 0.0),
+        velocity: Vec2::new(600.0, 0.0),
+        damage: 10,
+        radius: 10.0,
+        pierce: 0,
+    });
+    step(&mut app, 30);
+    let health = app.world_mut().query::<&Player>().single(app.world()).health;
+    assert_eq!(health, Player::default().health - 10);
+}
+
+#[test]
+fn archers_shoot_and_shamans_heal() {
+    let mut app = headless_app();
+    app.world_mut().query::<&mut Weapons>().single_mut(app.world_mut()).equipped.clear();
+    let player = player_position(&mut app).truncate();
+    app.world_mut().spawn(enemy_bundle(EnemyType::new("archer"), EnemyState::default(), player + Vec2::new(400.0, 0.0), 20));
+    app.world_mut().spawn(enemy_bundle(EnemyType::new("shaman"), EnemyState::default(), player + Vec2::new(0.0, -300.0), 25));
+    // Tougher than its definition, as if spawned at night
+    let hurt = app.world_mut().spawn(enemy_bundle(EnemyType::new("slave"), EnemyState::default(), player + Vec2::new(0.0, -350.0), 40)).id();
+    app.world_mut().get_mut::<Enemy>(hurt).unwrap().health = 5;
+
+    step(&mut app, 60);
+    let health = app.world_mut().query::<&Player>().single(app.world()).health;
+    assert!(health < Player::default().health);
+    assert!(app.world().get::<Enemy>(hurt).unwrap().health > 5);
+
+    // Heals back to the health it spawned with, and no further
+    app.world_mut().get_mut::<Enemy>(hurt).unwrap().health = 35;
+    step(&mut app, (3.0 * TICK_RATE) as usize + 2);
+    assert_eq!(app.world().get::<Enemy>(hurt).unwrap().health, 40);
+}
+
+#[test]
+fn night_waves_are_bigger() {
+    let frames = (SPAWN_INTERVAL as f64 * TICK_RATE).ceil() as usize + 2;
+    let mut day = headless_app();
+    step(&mut day, frames);
+
+    let mut night = headless_app();
+    night.insert_resource(GameClock { time_of_day: 0.0, ..default() });
+    assert!(night.world().resource::<GameClock>().is_night());
+    step(&mut night, frames);
+    assert!(enemy_count(&mut night) > enemy_count(&mut day));
+}
+
+#[test]
+fn weather_follows_the_seed() {
+    let mut app = headless_app();
+    step(&mut app, 120);
+    let weather = *app.world().resource::<WeatherState>();
+    assert_eq!(weather, WeatherState::restore(WorldSeed(1), weather.elapsed()));
+
+    let sequence = |seed| (1..40)
+        .map(|period| WeatherState::restore(WorldSeed(seed), period as f32 * WEATHER_DURATION).current)
+        .collect::<Vec<_>>();
+    assert_eq!(sequence(7), sequence(7));
+    assert_ne!(sequence(7), sequence(8));
+}
+
+#[test]
+fn nearby_enemy_chases_player() {
+    let mut app = headless_app();
+    let player = player_position(&mut app);
+    let start = player + Vec3::new(ENEMY_CHASE_RANGE / 2.0, 0.0, 0.0);
+    let enemy = app.world_mut().spawn(
+        enemy_bundle(EnemyType::new("slave"), EnemyState::default(), start.truncate(), 30),
+    ).id();
+
+    step(&mut app, 5);
+
+    let position = app.world().get::<Transform>(enemy).unwrap().translation;
+    assert!(position.distance(player) < start.distance(player));
+    assert_eq!(app.world().get::<EnemyState>(enemy), Some(&EnemyState::RunLeft));
+}
+
+fn scripted_run(seed: WorldSeed) -> (Vec3, Vec<Vec3>, Vec<(i32, i32, String)>) {
+    let mut app = seeded_app(seed);
+    let script = [
+        PlayerInput { right: true, ..default() },
+        PlayerInput { up: true, sprint: true, ..default() },
+        PlayerInput { left: true, down: true, ..default() },
+        PlayerInput::default(),
+    ];
+    for input in script {
+        app.insert_resource(input);
+        step(&mut app, 90);
+    }
+
+    let enemies = app.world_mut()
+        .query_filtered::<&Transform, With<Enemy>>()
+        .iter(app.world())
+        .map(|transform| transform.translation)
+        .collect();
+    let terrain = app.world().resource::<TerrainMap>()
+        .tiles()
+        .map(|((x, y), tile)| (x, y, format!("{:?}", tile)))
+        .collect();
+    (player_position(&mut app), enemies, terrain)
+}
+
+#[test]
+fn same_seed_and_input_is_bit_identical() {
+    let first = scripted_run(WorldSeed(42));
+    let second = scripted_run(WorldSeed(42));
+    assert!(!first.1.is_empty());
+    assert_eq!(first, second);
+
+    let other = scripted_run(WorldSeed(43));
+    assert_ne!(first.2, other.2);
+}
+
+fn play_back(replay: Replay) -> ReplayResult {
+    let mut app = build_app(replay.seed);
+    let ticks = replay.inputs.len();
+    app.add_plugins(ReplayPlugin { mode: ReplayMode::Playback(replay) });
+    step(&mut app, ticks + 5);
+    app.world_mut().remove_resource::<ReplayResult>().expect("replay did not finish")
+}
+
+#[test]
+fn recorded_replay_reproduces_final_state() {
+    let path = std::env::temp_dir().join(format!("orcwars-test-{}.orcreplay", std::process::id()));
+    let mut app = build_app(WorldSeed(7));
+    app.add_plugins(ReplayPlugin { mode: ReplayMode::Record(path.clone()) });
+    app.update();
+    let script = [
+        PlayerInput { right: true, sprint: true, ..default() },
+        PlayerInput { down: true, move_slot: Some((0, 5)), use_slot: Some(2), ..default() },
+    ];
+    for input in script {
+        app.insert_resource(input);
+        step(&mut app, 150);
+    }
+    app.world_mut().send_event(AppExit::Success);
+    app.update();
+
+    let replay = Replay::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(replay.seed, WorldSeed(7));
+    assert_eq!(replay.tick_rate, TICK_RATE);
+    assert!(replay.inputs.contains(&PlayerInput { down: true, move_slot: Some((0, 5)), use_slot: Some(2), ..default() }));
+    assert_eq!(play_back(replay.clone()), ReplayResult::Matched);
+
+    let tampered = Replay { final_hash: replay.final_hash ^ 1, ..replay };
+    assert!(matches!(play_back(tampered), ReplayResult::Mismatched { .. }));
+}
+
+#[test]
+fn saved_game_loads_back() {
+    let dir = std::env::temp_dir().join(format!("orcwars-test-saves-{}", std::process::id()));
+    let mut app = build_app(WorldSeed(3));
+    app.insert_resource(SaveDir(dir.clone()))
+        .add_plugins(SavePlugin::headless());
+    app.update();
+    let start = world_to_grid(player_position(&mut app).truncate());
+    app.world_mut().resource_mut::<TerrainMap>().set_vegetation(5, 5, Some(2));
+    app.insert_resource(PlayerInput { up: true, ..default() });
+    step(&mut app, (SPAWN_INTERVAL as f64 * TICK_RATE) as usize * 2);
+
+    app.world_mut().send_event(SaveGameEvent(SaveSlot::Manual(1)));
+    app.update();
+    let saved_position = player_position(&mut app);
+    let saved_enemies = enemy_count(&mut app);
+    let saved_wave = app.world().resource::<WaveState>().wave;
+    assert!(saved_enemies > 0);
+
+    app.world_mut().resource_mut::<TerrainMap>().set_vegetation(5, 5, None);
+    app.insert_resource(PlayerInput { left: true, ..default() });
+    step(&mut app, 120);
+    assert_ne!(player_position(&mut app), saved_position);
+
+    app.insert_resource(PlayerInput::default());
+    app.world_mut().send_event(LoadGameEvent(SaveSlot::Manual(1)));
+    app.update();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(player_position(&mut app), saved_position);
+    assert_eq!(enemy_count(&mut app), saved_enemies);
+    assert_eq!(app.world().resource::<WaveState>().wave, saved_wave);
+    let terrain = app.world().resource::<TerrainMap>();
+    assert_eq!(terrain.tile(5, 5).unwrap().vegetation, Some(2));
+    assert!(terrain.is_explored(start.0, start.1));
+}
+
+#[test]
+fn version_1_saves_are_migrated() {
+    let path = std::env::temp_dir().join(format!("orcwars-test-v1-{}.ron", std::process::id()));
+    std::fs::write(&path, r#"(
+    version: 1,
+    seed: 3,
+    terrain_edits: [(x: 5, y: 5, vegetation: None)],
+    player: (position: (10000.0, 10000.0), health: 80, stamina: 50.0),
+    enemies: [
+        (enemy_type: Slave, state: RunLeft, position: (10300.0, 10000.0), health: 30),
+        (enemy_type: MiniBoss, state: Alive, position: (9000.0, 10000.0), health: 100),
+        (enemy_type: Archer, state: IdleFront, position: (10000.0, 10400.0), health: 20),
+        (enemy_type: Shaman, state: IdleBack, position: (10000.0, 9600.0), health: 25),
+        (enemy_type: Brute, state: RunRight, position: (9700.0, 10000.0), health: 60),
+    ],
+    wave: (wave: 2, kills: 3, elapsed: 40.0, time_in_wave: 5.0),
+)"#).unwrap();
+
+    let save = SaveGame::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(save.version, SAVE_VERSION);
+    let types: Vec<_> = save.enemies.iter().map(|enemy| enemy.enemy_type.clone()).collect();
+    let expected = ["slave", "mini_boss", "archer", "shaman", "brute"].map(EnemyType::new);
+    assert_eq!(types, expected);
+    assert_eq!(save.player.health, 80);
+}
+
+#[test]
+fn player_explores_around_itself() {
+    let mut app = headless_app();
+    app.update();
+
+    let start = world_to_grid(player_position(&mut app).truncate());
+    let terrain = app.world().resource::<TerrainMap>();
+    assert!(terrain.is_explored(start.0, start.1));
+    assert!(!terrain.is_explored(start.0 + VISION_RADIUS * 3, start.1));
+
+    // Clear the way, trees and rocks would stop the player
+    let mut terrain = app.world_mut().resource_mut::<TerrainMap>();
+    for x in start.0..start.0 + VISION_RADIUS * 3 {
+        terrain.set_vegetation(x, start.1, None);
+    }
+    app.insert_resource(PlayerInput { right: true, sprint: true, ..default() });
+    step(&mut app, 120);
+    let terrain = app.world().resource::<TerrainMap>();
+    assert!(terrain.is_explored(start.0 + VISION_RADIUS * 3, start.1));
+}